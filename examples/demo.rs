@@ -0,0 +1,92 @@
+use anyhow::Result;
+use typestate_test::{Client, Mime, RequestBuilder};
+
+fn main() -> Result<()> {
+    // When building a GET, `body()` cannot be called, and a RequestBuilder with NoBody is returned
+    let req = RequestBuilder::new()
+        .get()
+        .url("https://www.google.com")?
+        .header("Token", "zxcvasdv")?
+        .header("user-agent", "chrome/4.20.69")?
+        // .body("asdf") // throws a compiler error since `.get()` returns a RequestBuilder with a NoBody
+        .build();
+
+    println!("** GET Request **");
+    println!(
+        "{:?} {} {:?} {:?} \n",
+        req.method(),
+        req.url(),
+        req.headers(),
+        req.body()
+    );
+
+    // When building a POST and calling `body()` a RequestBuilder with Body is returned
+    let req = RequestBuilder::new()
+        .url("https://www.google.com")?
+        .post()
+        .header("Token", "zxcvasdv")?
+        .header("user-agent", "chrome/4.20.69")?
+        .body("asdf")
+        .content_type(Mime::TEXT_PLAIN) // a Body can't be built until its content type is set
+        .build();
+
+    println!("** POST Request **");
+    println!(
+        "{:?} {} {:?} {:?} \n",
+        req.method(),
+        req.url(),
+        req.headers(),
+        req.body()
+    );
+
+    // When building a POST without calling `body()` a RequestBuilder with MissingBody is returned
+    let req = RequestBuilder::new()
+        .url("https://www.google.com")?
+        .post()
+        .header("Token", "zxcvasdv")?
+        .header("user-agent", "chrome/4.20.69")?
+        // .body("asdf") // Not setting a body, means a RequestBuilder with MissingBody is returned
+        .build();
+
+    println!("** POST Request with missing body **");
+    println!(
+        "{:?} {} {:?} {:?} \n",
+        req.method(),
+        req.url(),
+        req.headers(),
+        req.body()
+    );
+
+    // When building a PUT, `build()` is only available once `body()` has been called
+    let req = RequestBuilder::new()
+        .url("https://www.google.com")?
+        .put()
+        .header("Token", "zxcvasdv")?
+        .text("asdf") // removing this throws a compiler error since `.put()` returns a RequestBuilder with PendingBody
+        .build();
+
+    println!("** PUT Request **");
+    println!(
+        "{:?} {} {:?} {:?} \n",
+        req.method(),
+        req.url(),
+        req.headers(),
+        req.body()
+    );
+
+    // A Client fills in the base URL and default headers, so requests start in the Url state
+    let client =
+        Client::new("https://www.google.com")?.default_header("user-agent", "chrome/4.20.69")?;
+    let req = client.get("/search")?.query("q", "typestate").build();
+
+    println!("** GET Request from a Client **");
+    println!(
+        "{:?} {} {:?} {:?} \n",
+        req.method(),
+        req.url(),
+        req.headers(),
+        req.body()
+    );
+
+    Ok(())
+}
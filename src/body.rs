@@ -0,0 +1,312 @@
+#[cfg(feature = "serde")]
+use std::borrow::Cow;
+use std::fmt;
+use std::fs::File;
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+
+/// The contents of a request body
+///
+/// Either bytes held in memory, a file that is only opened when the body is read, or a stream
+/// that can be read exactly once.
+///
+/// ```
+/// use typestate_test::body::Payload;
+///
+/// let payload = Payload::stream(["hello ", "world"]);
+/// assert_eq!(payload.into_bytes()?, b"hello world");
+/// # Ok::<(), std::io::Error>(())
+/// ```
+pub struct Payload(Inner);
+
+enum Inner {
+    Bytes(Vec<u8>),
+    File(PathBuf),
+    Reader(Box<dyn Read + Send>),
+}
+
+impl Payload {
+    /// A body held in memory
+    pub fn bytes(bytes: impl Into<Vec<u8>>) -> Self {
+        Payload(Inner::Bytes(bytes.into()))
+    }
+
+    /// A body read from `path` when the request is sent
+    pub fn file(path: impl Into<PathBuf>) -> Self {
+        Payload(Inner::File(path.into()))
+    }
+
+    /// A body streamed from `reader` when the request is sent
+    pub fn reader(reader: impl Read + Send + 'static) -> Self {
+        Payload(Inner::Reader(Box::new(reader)))
+    }
+
+    /// A body streamed from the chunks of `chunks` when the request is sent
+    pub fn stream<I>(chunks: I) -> Self
+    where
+        I: IntoIterator,
+        I::IntoIter: Send + 'static,
+        I::Item: AsRef<[u8]>,
+    {
+        Payload::reader(ChunkReader {
+            chunks: chunks.into_iter(),
+            current: Vec::new(),
+            pos: 0,
+        })
+    }
+
+    /// The bytes of an in-memory body
+    pub fn as_bytes(&self) -> Option<&[u8]> {
+        match &self.0 {
+            Inner::Bytes(bytes) => Some(bytes),
+            _ => None,
+        }
+    }
+
+    /// The bytes of an in-memory body, if they are valid UTF-8
+    pub fn as_str(&self) -> Option<&str> {
+        self.as_bytes()
+            .and_then(|bytes| std::str::from_utf8(bytes).ok())
+    }
+
+    /// The path of a file body
+    pub fn path(&self) -> Option<&Path> {
+        match &self.0 {
+            Inner::File(path) => Some(path),
+            _ => None,
+        }
+    }
+
+    /// Whether this body is a stream, whose length isn't known until it has been read
+    pub fn is_stream(&self) -> bool {
+        matches!(self.0, Inner::Reader(_))
+    }
+
+    /// The length of the body, reading a file's metadata if needed
+    ///
+    /// Returns `Ok(None)` for streams.
+    pub fn content_length(&self) -> io::Result<Option<u64>> {
+        match &self.0 {
+            Inner::Bytes(bytes) => Ok(Some(bytes.len() as u64)),
+            Inner::File(path) => Ok(Some(std::fs::metadata(path)?.len())),
+            Inner::Reader(_) => Ok(None),
+        }
+    }
+
+    /// Opens the body for reading
+    pub fn into_reader(self) -> io::Result<Box<dyn Read + Send>> {
+        match self.0 {
+            Inner::Bytes(bytes) => Ok(Box::new(io::Cursor::new(bytes))),
+            Inner::File(path) => Ok(Box::new(File::open(path)?)),
+            Inner::Reader(reader) => Ok(reader),
+        }
+    }
+
+    /// Reads the whole body into memory
+    pub fn into_bytes(self) -> io::Result<Vec<u8>> {
+        match self.0 {
+            Inner::Bytes(bytes) => Ok(bytes),
+            Inner::File(path) => std::fs::read(path),
+            Inner::Reader(mut reader) => {
+                let mut bytes = Vec::new();
+                reader.read_to_end(&mut bytes)?;
+                Ok(bytes)
+            }
+        }
+    }
+}
+
+impl From<Vec<u8>> for Payload {
+    fn from(bytes: Vec<u8>) -> Self {
+        Payload::bytes(bytes)
+    }
+}
+
+impl From<&[u8]> for Payload {
+    fn from(bytes: &[u8]) -> Self {
+        Payload::bytes(bytes)
+    }
+}
+
+impl From<String> for Payload {
+    fn from(text: String) -> Self {
+        Payload::bytes(text)
+    }
+}
+
+impl From<&str> for Payload {
+    fn from(text: &str) -> Self {
+        Payload::bytes(text)
+    }
+}
+
+impl fmt::Debug for Payload {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.0 {
+            Inner::Bytes(bytes) => match std::str::from_utf8(bytes) {
+                Ok(text) => f.debug_tuple("Bytes").field(&text).finish(),
+                Err(_) => f.debug_tuple("Bytes").field(bytes).finish(),
+            },
+            Inner::File(path) => f.debug_tuple("File").field(path).finish(),
+            Inner::Reader(_) => f.write_str("Stream"),
+        }
+    }
+}
+
+// Streams can't be compared without consuming them, so they never equal anything
+impl PartialEq for Payload {
+    fn eq(&self, other: &Self) -> bool {
+        match (&self.0, &other.0) {
+            (Inner::Bytes(a), Inner::Bytes(b)) => a == b,
+            (Inner::File(a), Inner::File(b)) => a == b,
+            _ => false,
+        }
+    }
+}
+
+/// How a `Payload` is stored: text if it's UTF-8, and bytes otherwise
+///
+/// File bodies are stored by their contents, never their path, so a stored request can't make
+/// the host that loads it upload one of its own files.
+#[cfg(feature = "serde")]
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum Stored<'a> {
+    Text(Cow<'a, str>),
+    Bytes(Cow<'a, [u8]>),
+}
+
+#[cfg(feature = "serde")]
+impl<'a> Stored<'a> {
+    fn new(bytes: Cow<'a, [u8]>) -> Self {
+        match bytes {
+            Cow::Borrowed(bytes) => match std::str::from_utf8(bytes) {
+                Ok(text) => Stored::Text(Cow::Borrowed(text)),
+                Err(_) => Stored::Bytes(Cow::Borrowed(bytes)),
+            },
+            Cow::Owned(bytes) => match String::from_utf8(bytes) {
+                Ok(text) => Stored::Text(Cow::Owned(text)),
+                Err(err) => Stored::Bytes(Cow::Owned(err.into_bytes())),
+            },
+        }
+    }
+}
+
+/// File bodies are read when serialized; streams can't be serialized, since that would consume
+/// them
+#[cfg(feature = "serde")]
+impl serde::Serialize for Payload {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let stored = match &self.0 {
+            Inner::Bytes(bytes) => Stored::new(Cow::Borrowed(bytes)),
+            Inner::File(path) => Stored::new(Cow::Owned(
+                std::fs::read(path).map_err(serde::ser::Error::custom)?,
+            )),
+            Inner::Reader(_) => {
+                return Err(serde::ser::Error::custom(
+                    "stream bodies can't be serialized without consuming them",
+                ))
+            }
+        };
+        stored.serialize(serializer)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Payload {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Ok(match Stored::deserialize(deserializer)? {
+            Stored::Text(text) => Payload::bytes(text.into_owned()),
+            Stored::Bytes(bytes) => Payload::bytes(bytes),
+        })
+    }
+}
+
+/// A JSON body couldn't be written or read back
+#[cfg(feature = "json")]
+#[derive(Debug)]
+pub enum JsonError {
+    /// The request has no body
+    Missing,
+    /// The body is a stream, which can't be read without consuming it
+    Streaming,
+    /// The body is a file that couldn't be read
+    Io(io::Error),
+    /// The value couldn't be serialized, or the body isn't valid JSON for the target type
+    Serde(serde_json::Error),
+}
+
+#[cfg(feature = "json")]
+impl fmt::Display for JsonError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            JsonError::Missing => f.write_str("request has no body"),
+            JsonError::Streaming => f.write_str("streaming bodies can't be read as JSON"),
+            JsonError::Io(err) => write!(f, "failed to read body: {err}"),
+            JsonError::Serde(err) => write!(f, "invalid JSON body: {err}"),
+        }
+    }
+}
+
+#[cfg(feature = "json")]
+impl std::error::Error for JsonError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            JsonError::Io(err) => Some(err),
+            JsonError::Serde(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(feature = "json")]
+impl Payload {
+    /// Serializes `value` into an in-memory JSON body
+    pub fn json<T: serde::Serialize + ?Sized>(value: &T) -> Result<Self, JsonError> {
+        serde_json::to_vec(value)
+            .map(Payload::bytes)
+            .map_err(JsonError::Serde)
+    }
+
+    /// Deserializes an in-memory or file body as JSON
+    pub fn to_json<T: serde::de::DeserializeOwned>(&self) -> Result<T, JsonError> {
+        match &self.0 {
+            Inner::Bytes(bytes) => serde_json::from_slice(bytes).map_err(JsonError::Serde),
+            Inner::File(path) => {
+                let bytes = std::fs::read(path).map_err(JsonError::Io)?;
+                serde_json::from_slice(&bytes).map_err(JsonError::Serde)
+            }
+            Inner::Reader(_) => Err(JsonError::Streaming),
+        }
+    }
+}
+
+/// Adapts an iterator of chunks into `Read`
+struct ChunkReader<I: Iterator> {
+    chunks: I,
+    current: Vec<u8>,
+    pos: usize,
+}
+
+impl<I> Read for ChunkReader<I>
+where
+    I: Iterator,
+    I::Item: AsRef<[u8]>,
+{
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        while self.pos == self.current.len() {
+            match self.chunks.next() {
+                Some(chunk) => {
+                    self.current.clear();
+                    self.current.extend_from_slice(chunk.as_ref());
+                    self.pos = 0;
+                }
+                None => return Ok(0),
+            }
+        }
+        let n = buf.len().min(self.current.len() - self.pos);
+        buf[..n].copy_from_slice(&self.current[self.pos..self.pos + n]);
+        self.pos += n;
+        Ok(n)
+    }
+}
@@ -0,0 +1,871 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+#[cfg(feature = "json")]
+use crate::body::JsonError;
+use crate::body::Payload;
+use crate::curl::{self, CurlError};
+#[cfg(feature = "json")]
+use crate::har::{self, HarError, HarRequest};
+use crate::header::{
+    Accept, Authorization, ContentLength, ContentType, HeaderMap, HeaderName, HeaderValue,
+    IfNoneMatch, InvalidHeader, InvalidHeaderValue, ProtectedHeader, Range, TypedHeader, UserAgent,
+    CONTENT_TYPE,
+};
+use crate::http1::SendError;
+use crate::method::{
+    method_of, BodyRule, BodyRuleError, Connect, Delete, Get, Head, HttpMethod, Method,
+    NoBodyAllowed, OptionalBody, Options, Patch, Post, Put, RequiredBody, Trace,
+};
+use crate::mime::Mime;
+use crate::multipart::Multipart;
+use crate::reader::ResponseReader;
+use crate::request::Request;
+use crate::response::Response;
+use crate::state::{
+    AcceptsBody, ApplyBodyPolicy, Body, Checked, ContentTypeSet, MissingBody, MissingContentType,
+    MissingMethod, MissingUrl, NoBody, Unchecked, Url, UrlTemplate, WithoutBody,
+};
+use crate::template::TemplateError;
+use crate::transport::Transport;
+#[cfg(feature = "serde")]
+use crate::url::QueryError;
+use crate::url::{form_urlencode, IntoUrl, ParseError};
+
+// Request Builder
+#[derive(Default)]
+pub struct RequestBuilder<U, M, B, C = MissingContentType> {
+    url: U,
+    method: M,
+    headers: HeaderMap,
+    body: B,
+    content_type: C,
+    timeout: Option<Duration>,
+    /// Set when the builder came from a `Client`, which `send()` then goes through
+    transport: Option<Arc<dyn Transport + Send + Sync>>,
+    /// Headers still holding a `Client`'s default values, which `header()` replaces
+    defaults: Vec<HeaderName>,
+}
+
+// Default state is always going to start off without a Url, Method, or Body
+impl RequestBuilder<MissingUrl, MissingMethod, MissingBody> {
+    pub fn new() -> Self {
+        RequestBuilder::default()
+    }
+
+    /// Parses a `curl` command line into a builder ready to `build()`
+    ///
+    /// Understands `-X`, `-H`, `-d`/`--data-raw`, `--data-binary` (including `@file`), `-u`,
+    /// `-I` and `--url`, and ignores output flags such as `-sSL`. As with curl, data without
+    /// `-X` makes a POST with an `application/x-www-form-urlencoded` body.
+    ///
+    /// ```
+    /// use typestate_test::{Method, RequestBuilder};
+    ///
+    /// let req = RequestBuilder::from_curl(
+    ///     "curl -X PUT https://example.com/items/1 -H 'Content-Type: application/json' -d '{}'",
+    /// )?
+    /// .build();
+    ///
+    /// assert_eq!(req.method(), &Method::PUT);
+    /// assert_eq!(req.body().and_then(|body| body.as_str()), Some("{}"));
+    ///
+    /// // A GET can't have a body, whichever way it was written
+    /// assert!(RequestBuilder::from_curl("curl -X GET https://example.com -d a=1").is_err());
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn from_curl(command: &str) -> Result<RequestBuilder<Url, Method, Checked>, CurlError> {
+        curl::from_curl(command)
+    }
+
+    /// Reads a HAR entry's `request` into a builder ready to `build()`
+    ///
+    /// HTTP/2 pseudo-headers and `Content-Length` are dropped, as is `Host` if it matches the
+    /// URL. `postData` is checked against the method's body rule, so a GET with `postData` is
+    /// an error.
+    #[cfg(feature = "json")]
+    pub fn from_har(
+        request: &HarRequest,
+    ) -> Result<RequestBuilder<Url, Method, Checked>, HarError> {
+        har::from_har(request)
+    }
+}
+
+// Basic functions for building request
+impl<U, M, B, C> RequestBuilder<U, M, B, C> {
+    /// Adds a header to a request, keeping any values already set for the same name, except a
+    /// `Client`'s default, which it replaces
+    ///
+    /// Names are case-insensitive, and an invalid name or a value containing CR, LF or another
+    /// control character is rejected.
+    pub fn header<K, V>(mut self, key: K, value: V) -> Result<Self, InvalidHeader>
+    where
+        K: TryInto<HeaderName>,
+        V: TryInto<HeaderValue>,
+        InvalidHeader: From<K::Error> + From<V::Error>,
+    {
+        let (name, value) = (key.try_into()?, value.try_into()?);
+        match self.defaults.iter().position(|default| *default == name) {
+            Some(i) => {
+                self.defaults.swap_remove(i);
+                self.headers.insert(name, value);
+            }
+            None => self.headers.append(name, value),
+        }
+        Ok(self)
+    }
+
+    /// Removes every value of the header `name`, or returns an error for `Content-Type`
+    ///
+    /// A body can only be built once `content_type()` was called, so removing it afterwards
+    /// isn't allowed.
+    ///
+    /// ```
+    /// use typestate_test::RequestBuilder;
+    ///
+    /// let builder = RequestBuilder::new()
+    ///     .post()
+    ///     .url("https://example.com")?
+    ///     .header("X-Debug", "1")?
+    ///     .text("hello")
+    ///     .remove_header("x-debug")?;
+    ///
+    /// assert!(builder.remove_header("Content-Type").is_err());
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn remove_header(mut self, name: &str) -> Result<Self, ProtectedHeader> {
+        if name.eq_ignore_ascii_case(CONTENT_TYPE.as_str()) {
+            return Err(ProtectedHeader(CONTENT_TYPE));
+        }
+        self.headers.remove(name);
+        Ok(self)
+    }
+
+    /// The headers set so far
+    pub fn headers(&self) -> &HeaderMap {
+        &self.headers
+    }
+
+    /// Sets the typed header `H`, replacing any values it already had
+    pub fn typed_header<H: TypedHeader>(mut self, header: H) -> Self {
+        self.headers.typed_insert(&header);
+        self
+    }
+
+    /// Sets `Content-Type`, which a request with a body needs before it can be built
+    pub fn content_type(mut self, mime: Mime) -> RequestBuilder<U, M, B, ContentTypeSet> {
+        self.headers.typed_insert(&ContentType(mime));
+        RequestBuilder {
+            url: self.url,
+            method: self.method,
+            headers: self.headers,
+            body: self.body,
+            content_type: ContentTypeSet,
+            timeout: self.timeout,
+            transport: self.transport,
+            defaults: self.defaults,
+        }
+    }
+
+    /// Sets `Accept` to the given media types, in order of preference
+    pub fn accept(self, types: impl IntoIterator<Item = Mime>) -> Self {
+        self.typed_header(Accept(types.into_iter().collect()))
+    }
+
+    /// Sets `Authorization`
+    ///
+    /// ```
+    /// use typestate_test::header::Authorization;
+    /// use typestate_test::RequestBuilder;
+    ///
+    /// let req = RequestBuilder::new()
+    ///     .get()
+    ///     .url("https://example.com")?
+    ///     .authorization(Authorization::basic("aladdin", "opensesame")?)
+    ///     .build();
+    ///
+    /// assert_eq!(
+    ///     req.headers().get("authorization").unwrap().as_str(),
+    ///     "Basic YWxhZGRpbjpvcGVuc2VzYW1l"
+    /// );
+    /// assert_eq!(
+    ///     req.authorization()?.unwrap().username_password(),
+    ///     Some(("aladdin", "opensesame"))
+    /// );
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn authorization(self, authorization: Authorization) -> Self {
+        self.typed_header(authorization)
+    }
+
+    /// Sets `User-Agent`, or returns an error if `user_agent` contains a control character
+    pub fn user_agent<V>(self, user_agent: V) -> Result<Self, InvalidHeaderValue>
+    where
+        V: TryInto<HeaderValue>,
+        InvalidHeaderValue: From<V::Error>,
+    {
+        Ok(self.typed_header(UserAgent(user_agent.try_into()?)))
+    }
+
+    /// Sets `Content-Length`
+    pub fn content_length(self, length: u64) -> Self {
+        self.typed_header(ContentLength(length))
+    }
+
+    /// Sets `If-None-Match`
+    pub fn if_none_match(self, if_none_match: IfNoneMatch) -> Self {
+        self.typed_header(if_none_match)
+    }
+
+    /// Sets `Range`
+    pub fn range(self, range: Range) -> Self {
+        self.typed_header(range)
+    }
+
+    /// Makes `send()` go through `transport`, as for every builder a `Client` hands out
+    pub(crate) fn with_transport(mut self, transport: Arc<dyn Transport + Send + Sync>) -> Self {
+        self.transport = Some(transport);
+        self
+    }
+
+    /// Adds a `Client`'s default headers, which a later `header()` with the same name replaces
+    pub(crate) fn default_headers(mut self, headers: &HeaderMap) -> Self {
+        for (name, value) in headers.iter() {
+            self.headers.append(name.clone(), value.clone());
+            if !self.defaults.contains(name) {
+                self.defaults.push(name.clone());
+            }
+        }
+        self
+    }
+
+    /// Sets how long sending the request may take
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+}
+
+impl<M, B, C> RequestBuilder<MissingUrl, M, B, C> {
+    /// Returns a RequestBuilder with a parsed URL, or an error if `url` isn't a valid absolute URL
+    ///
+    /// The URL can only be set once; use `replace_url()` to change it afterwards:
+    ///
+    /// ```compile_fail,E0599
+    /// use typestate_test::RequestBuilder;
+    ///
+    /// RequestBuilder::new().url("https://example.com")?.url("https://example.org");
+    /// # Ok::<(), typestate_test::url::ParseError>(())
+    /// ```
+    pub fn url(self, url: impl IntoUrl) -> Result<RequestBuilder<Url, M, B, C>, ParseError> {
+        Ok(RequestBuilder {
+            url: url.into_url()?,
+            method: self.method,
+            headers: self.headers,
+            content_type: self.content_type,
+            timeout: self.timeout,
+            transport: self.transport,
+            defaults: self.defaults,
+            body: self.body,
+        })
+    }
+
+    /// Returns a RequestBuilder with a URL template whose `{name}` placeholders must all be
+    /// filled with `path_param()` and then `expand()`ed before `build()` is available
+    ///
+    /// ```
+    /// use typestate_test::RequestBuilder;
+    ///
+    /// let req = RequestBuilder::new()
+    ///     .get()
+    ///     .url_template("https://example.com/users/{id}/orders/{order_id}")?
+    ///     .path_param("id", 42)
+    ///     .path_param("order_id", "a/b")
+    ///     .expand()?
+    ///     .build();
+    ///
+    /// assert_eq!(req.url().path(), "/users/42/orders/a%2Fb");
+    /// # Ok::<(), typestate_test::template::TemplateError>(())
+    /// ```
+    ///
+    /// ```compile_fail,E0599
+    /// use typestate_test::RequestBuilder;
+    ///
+    /// RequestBuilder::new()
+    ///     .get()
+    ///     .url_template("https://example.com/users/{id}")?
+    ///     .path_param("id", 42)
+    ///     .build();
+    /// # Ok::<(), typestate_test::template::TemplateError>(())
+    /// ```
+    pub fn url_template(
+        self,
+        template: impl Into<String>,
+    ) -> Result<RequestBuilder<UrlTemplate, M, B, C>, TemplateError> {
+        Ok(RequestBuilder {
+            url: UrlTemplate::parse(template)?,
+            method: self.method,
+            headers: self.headers,
+            content_type: self.content_type,
+            timeout: self.timeout,
+            transport: self.transport,
+            defaults: self.defaults,
+            body: self.body,
+        })
+    }
+}
+
+impl<M, B, C> RequestBuilder<UrlTemplate, M, B, C> {
+    /// Fills the `{name}` placeholder with `value`, percent-encoded as a single path segment
+    pub fn path_param(mut self, name: impl Into<String>, value: impl ToString) -> Self {
+        self.url.push_param(name.into(), value.to_string());
+        self
+    }
+
+    /// Returns a RequestBuilder with the expanded URL, or an error if a param doesn't match a
+    /// placeholder or a placeholder was never filled
+    pub fn expand(self) -> Result<RequestBuilder<Url, M, B, C>, TemplateError> {
+        Ok(RequestBuilder {
+            url: self.url.expand()?,
+            method: self.method,
+            headers: self.headers,
+            content_type: self.content_type,
+            timeout: self.timeout,
+            transport: self.transport,
+            defaults: self.defaults,
+            body: self.body,
+        })
+    }
+}
+
+impl<M, B, C> RequestBuilder<Url, M, B, C> {
+    /// Replaces an already set URL
+    ///
+    /// Only available once a URL has been set, so it can't stand in for `url()`:
+    ///
+    /// ```compile_fail,E0599
+    /// use typestate_test::RequestBuilder;
+    ///
+    /// RequestBuilder::new().replace_url("https://example.com");
+    /// ```
+    pub fn replace_url(mut self, url: impl IntoUrl) -> Result<Self, ParseError> {
+        self.url = url.into_url()?;
+        Ok(self)
+    }
+
+    /// Appends a percent-encoded `key=value` pair to the URL's query
+    ///
+    /// Pairs keep their insertion order, repeated keys are kept, and any query already in the URL
+    /// comes first:
+    ///
+    /// ```
+    /// use typestate_test::RequestBuilder;
+    ///
+    /// let req = RequestBuilder::new()
+    ///     .get()
+    ///     .url("https://example.com/search?lang=en")?
+    ///     .query("q", "rust & typestate")
+    ///     .query_pairs([("tag", "a"), ("tag", "b")])
+    ///     .build();
+    ///
+    /// assert_eq!(req.url().query(), Some("lang=en&q=rust+%26+typestate&tag=a&tag=b"));
+    /// # Ok::<(), typestate_test::url::ParseError>(())
+    /// ```
+    pub fn query(mut self, key: impl AsRef<str>, value: impl AsRef<str>) -> Self {
+        self.url.append_query_pair(key.as_ref(), value.as_ref());
+        self
+    }
+
+    /// Appends every `(key, value)` pair to the URL's query, see `query()`
+    pub fn query_pairs<K, V>(mut self, pairs: impl IntoIterator<Item = (K, V)>) -> Self
+    where
+        K: AsRef<str>,
+        V: AsRef<str>,
+    {
+        for (key, value) in pairs {
+            self.url.append_query_pair(key.as_ref(), value.as_ref());
+        }
+        self
+    }
+
+    /// Serializes `query` (a struct, map or sequence of pairs) and appends it to the URL's query,
+    /// or returns an error if a value is itself a struct, map or sequence
+    #[cfg(feature = "serde")]
+    pub fn query_serialize<T: serde::Serialize + ?Sized>(
+        mut self,
+        query: &T,
+    ) -> Result<Self, QueryError> {
+        let encoded = serde_urlencoded::to_string(query).map_err(QueryError)?;
+        self.url.append_encoded_query(&encoded);
+        Ok(self)
+    }
+}
+
+impl<U, B, C> RequestBuilder<U, MissingMethod, B, C> {
+    /// Returns a RequestBuilder with the given method, moving the body into the state its `BodyPolicy` requires
+    ///
+    /// The URL, headers and body can be set in any order, but a body set before the method is
+    /// only accepted by methods that allow one:
+    ///
+    /// ```
+    /// use typestate_test::RequestBuilder;
+    ///
+    /// let req = RequestBuilder::new().text("x").post().url("https://example.com")?.build();
+    /// assert_eq!(req.body().and_then(|body| body.as_str()), Some("x"));
+    /// # Ok::<(), typestate_test::url::ParseError>(())
+    /// ```
+    ///
+    /// ```compile_fail,E0277
+    /// use typestate_test::RequestBuilder;
+    ///
+    /// // GET can't have a body, so it can't take one that was already set
+    /// RequestBuilder::new().body("x").get();
+    /// ```
+    ///
+    /// ```compile_fail,E0277
+    /// use typestate_test::RequestBuilder;
+    ///
+    /// RequestBuilder::new().body("x").head();
+    /// ```
+    ///
+    /// ```compile_fail,E0599
+    /// use typestate_test::RequestBuilder;
+    ///
+    /// RequestBuilder::new().get().body("x");
+    /// ```
+    ///
+    /// ```compile_fail,E0599
+    /// use typestate_test::RequestBuilder;
+    ///
+    /// // PUT must have a body
+    /// RequestBuilder::new().url("https://example.com")?.put().build();
+    /// # Ok::<(), typestate_test::url::ParseError>(())
+    /// ```
+    ///
+    /// ```compile_fail,E0599
+    /// use typestate_test::RequestBuilder;
+    ///
+    /// // The method can only be set once
+    /// RequestBuilder::new().post().get();
+    /// ```
+    ///
+    /// ```compile_fail,E0599
+    /// use typestate_test::RequestBuilder;
+    ///
+    /// RequestBuilder::new().url("https://example.com")?.body("x").build();
+    /// # Ok::<(), typestate_test::url::ParseError>(())
+    /// ```
+    pub fn method<M>(self, method: M) -> RequestBuilder<U, M, B::Output, C>
+    where
+        M: HttpMethod,
+        B: ApplyBodyPolicy<M::BodyPolicy>,
+    {
+        RequestBuilder {
+            url: self.url,
+            method,
+            headers: self.headers,
+            content_type: self.content_type,
+            timeout: self.timeout,
+            transport: self.transport,
+            defaults: self.defaults,
+            body: self.body.apply(),
+        }
+    }
+
+    /// GET request will never have a body, so return a RequestBuilder with a Method and NoBody type
+    pub fn get(self) -> RequestBuilder<U, Get, B::Output, C>
+    where
+        B: ApplyBodyPolicy<NoBodyAllowed>,
+    {
+        self.method(Get)
+    }
+
+    /// HEAD requests never have a body
+    pub fn head(self) -> RequestBuilder<U, Head, B::Output, C>
+    where
+        B: ApplyBodyPolicy<NoBodyAllowed>,
+    {
+        self.method(Head)
+    }
+
+    /// TRACE requests never have a body
+    pub fn trace(self) -> RequestBuilder<U, Trace, B::Output, C>
+    where
+        B: ApplyBodyPolicy<NoBodyAllowed>,
+    {
+        self.method(Trace)
+    }
+
+    /// CONNECT requests never have a body
+    pub fn connect(self) -> RequestBuilder<U, Connect, B::Output, C>
+    where
+        B: ApplyBodyPolicy<NoBodyAllowed>,
+    {
+        self.method(Connect)
+    }
+
+    /// POST requests may or may not have a body, so return a RequestBuilder with a Method, but any Body
+    pub fn post(self) -> RequestBuilder<U, Post, B::Output, C>
+    where
+        B: ApplyBodyPolicy<OptionalBody>,
+    {
+        self.method(Post)
+    }
+
+    /// DELETE requests may optionally have a body
+    pub fn delete(self) -> RequestBuilder<U, Delete, B::Output, C>
+    where
+        B: ApplyBodyPolicy<OptionalBody>,
+    {
+        self.method(Delete)
+    }
+
+    /// OPTIONS requests may optionally have a body
+    pub fn options(self) -> RequestBuilder<U, Options, B::Output, C>
+    where
+        B: ApplyBodyPolicy<OptionalBody>,
+    {
+        self.method(Options)
+    }
+
+    /// PUT requests must have a body, so `build()` is unavailable until `body()` is called
+    pub fn put(self) -> RequestBuilder<U, Put, B::Output, C>
+    where
+        B: ApplyBodyPolicy<RequiredBody>,
+    {
+        self.method(Put)
+    }
+
+    /// PATCH requests must have a body, so `build()` is unavailable until `body()` is called
+    pub fn patch(self) -> RequestBuilder<U, Patch, B::Output, C>
+    where
+        B: ApplyBodyPolicy<RequiredBody>,
+    {
+        self.method(Patch)
+    }
+}
+
+impl<U, C> RequestBuilder<U, MissingMethod, MissingBody, C> {
+    /// Sets a method only known at runtime along with the body, checking the same rules the
+    /// method states enforce at compile time
+    ///
+    /// Used when a request comes from text, such as `from_curl`. A body also needs a
+    /// `Content-Type` header, just as `build()` requires.
+    ///
+    /// ```
+    /// use typestate_test::{Method, Payload, RequestBuilder};
+    /// use typestate_test::method::BodyRuleError;
+    ///
+    /// let req = RequestBuilder::new()
+    ///     .url("https://example.com/files")?
+    ///     .method_and_body(Method::from_token("PROPFIND")?, None)?
+    ///     .build();
+    /// assert_eq!(req.method().as_str(), "PROPFIND");
+    ///
+    /// let get_with_body = RequestBuilder::new()
+    ///     .header("content-type", "text/plain")?
+    ///     .method_and_body(Method::GET, Some(Payload::from("x")));
+    /// assert_eq!(get_with_body.err(), Some(BodyRuleError::NotAllowed(Method::GET)));
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn method_and_body(
+        self,
+        method: Method,
+        body: Option<Payload>,
+    ) -> Result<RequestBuilder<U, Method, Checked, C>, BodyRuleError> {
+        match (method.body_rule(), &body) {
+            (BodyRule::NotAllowed, Some(_)) => return Err(BodyRuleError::NotAllowed(method)),
+            (BodyRule::Required, None) => return Err(BodyRuleError::Required(method)),
+            (_, Some(_)) if !self.headers.contains_key("content-type") => {
+                return Err(BodyRuleError::MissingContentType)
+            }
+            _ => {}
+        }
+        Ok(RequestBuilder {
+            url: self.url,
+            method,
+            headers: self.headers,
+            content_type: self.content_type,
+            timeout: self.timeout,
+            transport: self.transport,
+            defaults: self.defaults,
+            body: Checked(body),
+        })
+    }
+}
+
+impl<U, M, B: WithoutBody> RequestBuilder<U, M, B, MissingContentType> {
+    /// The headers set so far, for changes `header()` and `remove_header()` can't make
+    ///
+    /// Only available until a body or `Content-Type` is set, since either one relies on the
+    /// other being there.
+    ///
+    /// ```compile_fail,E0599
+    /// use typestate_test::RequestBuilder;
+    ///
+    /// let mut builder = RequestBuilder::new()
+    ///     .post()
+    ///     .url("https://example.com")?
+    ///     .text("hello");
+    /// builder.headers_mut().remove("content-type");
+    /// # Ok::<(), typestate_test::url::ParseError>(())
+    /// ```
+    pub fn headers_mut(&mut self) -> &mut HeaderMap {
+        &mut self.headers
+    }
+}
+
+impl<U, M, B: AcceptsBody, C> RequestBuilder<U, M, B, C> {
+    /// Return a RequestBuilder with a Body, which PUT/PATCH require before `build()`
+    pub fn body(self, body: impl Into<Payload>) -> RequestBuilder<U, M, Body, C> {
+        RequestBuilder {
+            url: self.url,
+            method: self.method,
+            headers: self.headers,
+            content_type: self.content_type,
+            timeout: self.timeout,
+            transport: self.transport,
+            defaults: self.defaults,
+            body: Body(body.into()),
+        }
+    }
+
+    /// Return a RequestBuilder with a plain text Body, setting `Content-Type: text/plain; charset=utf-8`
+    pub fn text(self, body: impl Into<String>) -> RequestBuilder<U, M, Body, ContentTypeSet> {
+        self.body(body.into()).content_type(text_plain_utf8())
+    }
+
+    /// Return a RequestBuilder with a JSON Body, setting `Content-Type: application/json`
+    ///
+    /// ```
+    /// # #[derive(serde::Serialize, serde::Deserialize, Debug, PartialEq)]
+    /// # struct User { name: String }
+    /// use typestate_test::RequestBuilder;
+    ///
+    /// let user = User { name: "ferris".into() };
+    /// let req = RequestBuilder::new()
+    ///     .post()
+    ///     .url("https://example.com/users")?
+    ///     .json(&user)?
+    ///     .build();
+    ///
+    /// assert_eq!(req.headers().get("content-type").unwrap().as_str(), "application/json");
+    /// assert_eq!(req.body_json::<User>()?, user);
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    #[cfg(feature = "json")]
+    pub fn json<T: serde::Serialize + ?Sized>(
+        self,
+        value: &T,
+    ) -> Result<RequestBuilder<U, M, Body, ContentTypeSet>, JsonError> {
+        Ok(self
+            .body(Payload::json(value)?)
+            .content_type(Mime::APPLICATION_JSON))
+    }
+
+    /// Return a RequestBuilder with an `application/x-www-form-urlencoded` Body
+    ///
+    /// ```
+    /// use typestate_test::RequestBuilder;
+    ///
+    /// let req = RequestBuilder::new()
+    ///     .post()
+    ///     .url("https://example.com/login")?
+    ///     .form(&[("user", "ferris"), ("note", "a&b c")])
+    ///     .build();
+    ///
+    /// assert_eq!(req.body().unwrap().as_str(), Some("user=ferris&note=a%26b+c"));
+    /// # Ok::<(), typestate_test::url::ParseError>(())
+    /// ```
+    pub fn form<K, V>(self, fields: &[(K, V)]) -> RequestBuilder<U, M, Body, ContentTypeSet>
+    where
+        K: AsRef<str>,
+        V: AsRef<str>,
+    {
+        self.body(encode_form(fields))
+            .content_type(Mime::APPLICATION_WWW_FORM_URLENCODED)
+    }
+
+    /// Return a RequestBuilder with a `multipart/form-data` Body
+    pub fn multipart(self, form: Multipart) -> RequestBuilder<U, M, Body, ContentTypeSet> {
+        let content_type = form.content_type();
+        self.body(form.into_payload()).content_type(content_type)
+    }
+}
+
+fn encode_form<K: AsRef<str>, V: AsRef<str>>(fields: &[(K, V)]) -> String {
+    let pairs: Vec<String> = fields
+        .iter()
+        .map(|(key, value)| {
+            format!(
+                "{}={}",
+                form_urlencode(key.as_ref()),
+                form_urlencode(value.as_ref())
+            )
+        })
+        .collect();
+    pairs.join("&")
+}
+
+fn text_plain_utf8() -> Mime {
+    Mime::TEXT_PLAIN
+        .with_param("charset", "utf-8")
+        .expect("charset=utf-8 is a valid parameter")
+}
+
+/// There are four states during build
+///   1. NoBody (`NoBodyAllowed` methods, e.g. GET)
+///   2. Body (`OptionalBody` and `RequiredBody` methods, e.g. POST, PUT), only once a content
+///      type has been set
+///   3. MissingBody (`OptionalBody` methods, e.g. POST)
+///   4. Checked (a runtime `Method`, after `method_and_body()`)
+///
+/// PendingBody (`RequiredBody` methods without a body) has no `build()`
+///
+/// ```compile_fail,E0599
+/// use typestate_test::RequestBuilder;
+///
+/// // A body needs a `content_type()`, or a typed body such as `text()`
+/// RequestBuilder::new().post().url("https://example.com")?.body("x").build();
+/// # Ok::<(), typestate_test::url::ParseError>(())
+/// ```
+impl<M: HttpMethod> RequestBuilder<Url, M, Body, ContentTypeSet> {
+    pub fn build(self) -> Request {
+        Request {
+            url: self.url,
+            method: method_of::<M>(),
+            headers: self.headers,
+            timeout: self.timeout,
+            body: Some(self.body.0),
+        }
+    }
+
+    /// Builds and sends the request, returning a reader for the response
+    ///
+    /// A builder from a `Client` is sent through the client's transport, any other over HTTP/1.1.
+    pub fn send(mut self) -> Result<ResponseReader<Unchecked, M>, SendError> {
+        let transport = self.transport.take();
+        send_with(transport, self.build()).map(ResponseReader::new)
+    }
+}
+impl<M: HttpMethod, C> RequestBuilder<Url, M, NoBody, C> {
+    pub fn build(self) -> Request {
+        Request {
+            url: self.url,
+            method: method_of::<M>(),
+            headers: self.headers,
+            timeout: self.timeout,
+            body: None,
+        }
+    }
+
+    /// Builds and sends the request, returning a reader for the response
+    ///
+    /// A builder from a `Client` is sent through the client's transport, any other over HTTP/1.1.
+    pub fn send(mut self) -> Result<ResponseReader<Unchecked, M>, SendError> {
+        let transport = self.transport.take();
+        send_with(transport, self.build()).map(ResponseReader::new)
+    }
+}
+impl<M: HttpMethod, C> RequestBuilder<Url, M, MissingBody, C> {
+    pub fn build(self) -> Request {
+        Request {
+            url: self.url,
+            method: method_of::<M>(),
+            headers: self.headers,
+            timeout: self.timeout,
+            body: None,
+        }
+    }
+
+    /// Builds and sends the request, returning a reader for the response
+    ///
+    /// A builder from a `Client` is sent through the client's transport, any other over HTTP/1.1.
+    pub fn send(mut self) -> Result<ResponseReader<Unchecked, M>, SendError> {
+        let transport = self.transport.take();
+        send_with(transport, self.build()).map(ResponseReader::new)
+    }
+}
+fn send_with(
+    transport: Option<Arc<dyn Transport + Send + Sync>>,
+    request: Request,
+) -> Result<Response, SendError> {
+    match transport {
+        Some(transport) => transport.send(request),
+        None => request.send(),
+    }
+}
+
+impl<C> RequestBuilder<Url, Method, Checked, C> {
+    pub fn build(self) -> Request {
+        Request {
+            url: self.url,
+            method: self.method,
+            headers: self.headers,
+            timeout: self.timeout,
+            body: self.body.0,
+        }
+    }
+}
+
+/// How a `Request`, or a builder after `method_and_body()`, is stored with serde
+#[cfg(feature = "serde")]
+#[derive(serde::Serialize)]
+pub(crate) struct SnapshotRef<'a> {
+    pub(crate) url: &'a Url,
+    pub(crate) method: &'a Method,
+    pub(crate) headers: &'a HeaderMap,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) body: Option<&'a Payload>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) timeout: Option<Duration>,
+}
+
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+struct Snapshot {
+    url: Url,
+    method: Method,
+    #[serde(default)]
+    headers: HeaderMap,
+    #[serde(default)]
+    body: Option<Payload>,
+    #[serde(default)]
+    timeout: Option<Duration>,
+}
+
+#[cfg(feature = "serde")]
+impl<C> serde::Serialize for RequestBuilder<Url, Method, Checked, C> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serde::Serialize::serialize(
+            &SnapshotRef {
+                url: &self.url,
+                method: &self.method,
+                headers: &self.headers,
+                body: self.body.0.as_ref(),
+                timeout: self.timeout,
+            },
+            serializer,
+        )
+    }
+}
+
+/// Checks the stored method and body with `method_and_body()`, so a GET with a body is rejected
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for RequestBuilder<Url, Method, Checked> {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let snapshot = <Snapshot as serde::Deserialize>::deserialize(deserializer)?;
+        RequestBuilder {
+            url: snapshot.url,
+            method: MissingMethod,
+            headers: snapshot.headers,
+            body: MissingBody,
+            content_type: MissingContentType,
+            timeout: snapshot.timeout,
+            transport: None,
+            defaults: Vec::new(),
+        }
+        .method_and_body(snapshot.method, snapshot.body)
+        .map_err(serde::de::Error::custom)
+    }
+}
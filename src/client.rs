@@ -0,0 +1,175 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::builder::RequestBuilder;
+use crate::header::{HeaderMap, HeaderName, HeaderValue, InvalidHeader};
+use crate::http1::SendError;
+use crate::method::{Connect, Delete, Get, Head, HttpMethod, Options, Patch, Post, Put, Trace};
+#[cfg(feature = "tokio")]
+use crate::pool::Pool;
+use crate::request::Request;
+use crate::response::Response;
+use crate::state::{ApplyBodyPolicy, MissingBody, Url};
+use crate::transport::{Http1, Transport};
+use crate::url::{IntoUrl, ParseError};
+
+/// The `RequestBuilder` a `Client` hands out for method `M`, already in the `Url` state
+pub type ClientRequest<M> =
+    RequestBuilder<Url, M, <MissingBody as ApplyBodyPolicy<<M as HttpMethod>::BodyPolicy>>::Output>;
+
+/// Shared settings that every request built from it inherits, and the `Transport` that
+/// `execute()` and its builders' `send()` send requests with
+///
+/// ```
+/// use std::time::Duration;
+/// use typestate_test::Client;
+///
+/// let client = Client::new("https://example.com/api/")?
+///     .default_header("user-agent", "chrome/4.20.69")?
+///     .timeout(Duration::from_secs(5));
+///
+/// let req = client.get("users/42")?.header("Token", "zxcvasdv")?.build();
+///
+/// assert_eq!(req.url().to_string(), "https://example.com/api/users/42");
+/// assert_eq!(req.headers().len(), 2);
+/// assert_eq!(req.timeout(), Some(Duration::from_secs(5)));
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+#[derive(Debug, Clone)]
+pub struct Client<T = Http1> {
+    base_url: Url,
+    headers: HeaderMap,
+    timeout: Option<Duration>,
+    #[cfg(feature = "tokio")]
+    pool: Pool,
+    transport: Arc<T>,
+}
+
+impl Client {
+    /// Returns a Client that resolves request paths against `base_url`
+    pub fn new(base_url: impl IntoUrl) -> Result<Self, ParseError> {
+        Ok(Client {
+            base_url: base_url.into_url()?,
+            headers: HeaderMap::new(),
+            timeout: None,
+            #[cfg(feature = "tokio")]
+            pool: Pool::new(),
+            transport: Arc::new(Http1),
+        })
+    }
+
+    /// Sends a request asynchronously over the network, reusing this client's idle connections
+    ///
+    /// Only a client sending over `Http1` has this, since a `Transport` can only send blocking.
+    #[cfg(feature = "tokio")]
+    pub async fn execute_async(&self, request: Request) -> Result<Response, SendError> {
+        self.pool.send(request).await
+    }
+}
+
+impl<T> Client<T> {
+    /// Sends requests through `transport` instead, e.g. a `MockTransport` in tests
+    pub fn transport<T2: Transport>(self, transport: T2) -> Client<T2> {
+        Client {
+            base_url: self.base_url,
+            headers: self.headers,
+            timeout: self.timeout,
+            #[cfg(feature = "tokio")]
+            pool: self.pool,
+            transport: Arc::new(transport),
+        }
+    }
+
+    /// Adds a header sent with every request, unless the request sets it with `header()`
+    pub fn default_header<K, V>(mut self, key: K, value: V) -> Result<Self, InvalidHeader>
+    where
+        K: TryInto<HeaderName>,
+        V: TryInto<HeaderValue>,
+        InvalidHeader: From<K::Error> + From<V::Error>,
+    {
+        self.headers.append(key.try_into()?, value.try_into()?);
+        Ok(self)
+    }
+
+    /// Sets the timeout every request starts with
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    pub fn base_url(&self) -> &Url {
+        &self.base_url
+    }
+
+    /// Sends a request through this client's transport, usually one built from its builders
+    pub fn execute(&self, request: Request) -> Result<Response, SendError>
+    where
+        T: Transport,
+    {
+        self.transport.send(request)
+    }
+}
+
+impl<T: Transport + Send + Sync + 'static> Client<T> {
+    /// Returns a RequestBuilder for `path` resolved against the base URL, with the method set
+    /// and the default headers and timeout applied; its `send()` goes through this client's
+    /// transport
+    ///
+    /// `path` can't change the scheme or authority, e.g. `https://other.com/` or `//other.com/`,
+    /// since the default headers may hold credentials meant only for the base URL.
+    pub fn request<M>(&self, method: M, path: &str) -> Result<ClientRequest<M>, ParseError>
+    where
+        M: HttpMethod,
+        MissingBody: ApplyBodyPolicy<M::BodyPolicy>,
+    {
+        let url = self.base_url.join(path)?;
+        if url.scheme() != self.base_url.scheme() || url.authority() != self.base_url.authority() {
+            return Err(ParseError::OtherOrigin);
+        }
+        let mut builder = RequestBuilder::new()
+            .url(url)?
+            .default_headers(&self.headers)
+            .with_transport(self.transport.clone())
+            .method(method);
+        if let Some(timeout) = self.timeout {
+            builder = builder.timeout(timeout);
+        }
+        Ok(builder)
+    }
+
+    pub fn get(&self, path: &str) -> Result<ClientRequest<Get>, ParseError> {
+        self.request(Get, path)
+    }
+
+    pub fn head(&self, path: &str) -> Result<ClientRequest<Head>, ParseError> {
+        self.request(Head, path)
+    }
+
+    pub fn trace(&self, path: &str) -> Result<ClientRequest<Trace>, ParseError> {
+        self.request(Trace, path)
+    }
+
+    pub fn connect(&self, path: &str) -> Result<ClientRequest<Connect>, ParseError> {
+        self.request(Connect, path)
+    }
+
+    pub fn post(&self, path: &str) -> Result<ClientRequest<Post>, ParseError> {
+        self.request(Post, path)
+    }
+
+    pub fn delete(&self, path: &str) -> Result<ClientRequest<Delete>, ParseError> {
+        self.request(Delete, path)
+    }
+
+    pub fn options(&self, path: &str) -> Result<ClientRequest<Options>, ParseError> {
+        self.request(Options, path)
+    }
+
+    pub fn put(&self, path: &str) -> Result<ClientRequest<Put>, ParseError> {
+        self.request(Put, path)
+    }
+
+    pub fn patch(&self, path: &str) -> Result<ClientRequest<Patch>, ParseError> {
+        self.request(Patch, path)
+    }
+}
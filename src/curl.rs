@@ -0,0 +1,313 @@
+//! Converting requests to and from `curl` command lines
+
+use std::fmt;
+use std::path::PathBuf;
+
+use crate::body::Payload;
+use crate::builder::RequestBuilder;
+use crate::header::{Authorization, InvalidHeader};
+use crate::method::{BodyRuleError, Method};
+use crate::request::Request;
+use crate::state::{Checked, Url};
+use crate::url::ParseError;
+
+/// A request couldn't be written as a `curl` command, or a command couldn't be read
+#[derive(Debug)]
+pub enum CurlError {
+    /// The body is a stream, or bytes that can't be passed as a shell argument
+    UnrepresentableBody,
+    /// Unbalanced quotes, or a command that doesn't start with `curl`
+    Syntax(&'static str),
+    UnknownFlag(String),
+    /// A flag such as `-H` was the last word
+    MissingValue(String),
+    /// A flag that is recognised but whose behaviour can't be reproduced
+    Unsupported(&'static str),
+    MissingUrl,
+    /// The `-X` value isn't a valid method token
+    InvalidMethod(String),
+    Url(ParseError),
+    /// A `-H` value without a `:`
+    MalformedHeader(String),
+    Header(InvalidHeader),
+    /// The body breaks the method's rules, e.g. `-X GET` with `-d`
+    Body(BodyRuleError),
+}
+
+impl fmt::Display for CurlError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CurlError::UnrepresentableBody => {
+                f.write_str("body can't be passed to curl as an argument")
+            }
+            CurlError::Syntax(reason) => write!(f, "invalid curl command: {reason}"),
+            CurlError::UnknownFlag(flag) => write!(f, "unknown curl flag `{flag}`"),
+            CurlError::MissingValue(flag) => write!(f, "curl flag `{flag}` needs a value"),
+            CurlError::Unsupported(reason) => write!(f, "unsupported curl usage: {reason}"),
+            CurlError::MissingUrl => f.write_str("curl command has no URL"),
+            CurlError::InvalidMethod(method) => write!(f, "invalid method {method:?}"),
+            CurlError::Url(err) => write!(f, "invalid URL: {err}"),
+            CurlError::MalformedHeader(header) => write!(f, "malformed header {header:?}"),
+            CurlError::Header(err) => err.fmt(f),
+            CurlError::Body(err) => err.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for CurlError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            CurlError::Url(err) => Some(err),
+            CurlError::Header(err) => Some(err),
+            CurlError::Body(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+/// Output-only flags that don't change the request
+const IGNORED_FLAGS: [&str; 7] = [
+    "--silent",
+    "--show-error",
+    "--verbose",
+    "--location",
+    "--include",
+    "--insecure",
+    "--compressed",
+];
+/// Short flags that can be grouped, e.g. `-sSL`
+const GROUPABLE_FLAGS: &str = "sSvLikI";
+
+pub(crate) fn to_curl(request: &Request) -> Result<String, CurlError> {
+    let mut args = vec!["curl".to_string()];
+    match (&request.method, &request.body) {
+        (Method::GET, None) => {}
+        // `-X HEAD` would make curl wait for a body that never comes
+        (Method::HEAD, None) => args.push("--head".to_string()),
+        (method, _) => args.extend(["-X".to_string(), quote(method.as_str())]),
+    }
+    args.push(quote(&request.url.to_string()));
+    for (name, value) in &request.headers {
+        args.extend(["-H".to_string(), quote(&format!("{name}: {value}"))]);
+    }
+    match request
+        .body
+        .as_ref()
+        .map(|body| (body.as_str(), body.path()))
+    {
+        None => {}
+        // `--data-raw` doesn't treat a leading `@` as a file name
+        Some((Some(text), _)) if !text.contains('\0') => {
+            args.extend(["--data-raw".to_string(), quote(text)]);
+        }
+        Some((None, Some(path))) => {
+            let path = path.to_str().ok_or(CurlError::UnrepresentableBody)?;
+            args.extend(["--data-binary".to_string(), format!("@{}", quote(path))]);
+        }
+        Some(_) => return Err(CurlError::UnrepresentableBody),
+    }
+    Ok(args.join(" "))
+}
+
+/// Quotes `arg` for a POSIX shell, leaving it bare if that's safe
+fn quote(arg: &str) -> String {
+    let safe = |c: char| c.is_ascii_alphanumeric() || "-_./:=%+,@".contains(c);
+    if !arg.is_empty() && arg.chars().all(safe) {
+        return arg.to_string();
+    }
+    format!("'{}'", arg.replace('\'', r"'\''"))
+}
+
+enum Data {
+    Text(String),
+    File(PathBuf),
+}
+
+pub(crate) fn from_curl(command: &str) -> Result<RequestBuilder<Url, Method, Checked>, CurlError> {
+    let mut words = split_words(command)?.into_iter();
+    if words.next().as_deref() != Some("curl") {
+        return Err(CurlError::Syntax("command doesn't start with `curl`"));
+    }
+
+    let mut method = None;
+    let mut url = None;
+    let mut headers = Vec::new();
+    let mut data = Vec::new();
+    let mut user = None;
+    let mut head = false;
+    while let Some(word) = words.next() {
+        let (flag, attached) = split_flag(&word);
+        let mut value = || {
+            attached
+                .clone()
+                .or_else(|| words.next())
+                .ok_or_else(|| CurlError::MissingValue(flag.to_string()))
+        };
+        match flag {
+            "-X" | "--request" => {
+                let token = value()?;
+                method =
+                    Some(Method::from_token(&token).map_err(|_| CurlError::InvalidMethod(token))?);
+            }
+            "-H" | "--header" => headers.push(value()?),
+            "-d" | "--data" | "--data-ascii" => {
+                let text = value()?;
+                if text.starts_with('@') {
+                    return Err(CurlError::Unsupported(
+                        "`-d @file` strips newlines from the file; use `--data-binary @file`",
+                    ));
+                }
+                data.push(Data::Text(text));
+            }
+            "--data-raw" => data.push(Data::Text(value()?)),
+            "--data-binary" => {
+                let text = value()?;
+                data.push(match text.strip_prefix('@') {
+                    Some(path) => Data::File(PathBuf::from(path)),
+                    None => Data::Text(text),
+                });
+            }
+            "-u" | "--user" => user = Some(value()?),
+            "--url" => url = Some(value()?),
+            "--head" => head = true,
+            flag if IGNORED_FLAGS.contains(&flag) => {}
+            flag if flag.len() > 1
+                && flag.starts_with('-')
+                && !flag.starts_with("--")
+                && flag[1..].chars().all(|c| GROUPABLE_FLAGS.contains(c)) =>
+            {
+                head |= flag.contains('I');
+            }
+            flag if flag.starts_with('-') && flag.len() > 1 => {
+                return Err(CurlError::UnknownFlag(flag.to_string()))
+            }
+            _ => url = Some(word.clone()),
+        }
+    }
+
+    // curl joins repeated `-d` values with `&`, but can't mix them with a file
+    let body = match data.as_slice() {
+        [] => None,
+        [Data::File(path)] => Some(Payload::file(path)),
+        texts => {
+            let texts = texts
+                .iter()
+                .map(|data| match data {
+                    Data::Text(text) => Ok(text.as_str()),
+                    Data::File(_) => Err(CurlError::Unsupported(
+                        "a file body can't be combined with other data",
+                    )),
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            Some(Payload::from(texts.join("&")))
+        }
+    };
+    let method = match method {
+        Some(method) => method,
+        None if head => Method::HEAD,
+        None if body.is_some() => Method::POST,
+        None => Method::GET,
+    };
+
+    let mut url = url.ok_or(CurlError::MissingUrl)?;
+    // Like curl, assume `http` when the URL has no scheme
+    if !url.contains("://") {
+        url.insert_str(0, "http://");
+    }
+    let mut builder = RequestBuilder::new().url(url).map_err(CurlError::Url)?;
+    for header in headers {
+        let (name, value) = header
+            .split_once(':')
+            .ok_or_else(|| CurlError::MalformedHeader(header.clone()))?;
+        builder = builder
+            .header(name.trim(), value.trim())
+            .map_err(CurlError::Header)?;
+    }
+    if let Some(user) = user {
+        let (username, password) = user.split_once(':').unwrap_or((&user, ""));
+        let authorization = Authorization::basic(username, password)
+            .map_err(|err| CurlError::Header(err.into()))?;
+        builder = builder.authorization(authorization);
+    }
+    if body.is_some() && !builder.headers().contains_key("content-type") {
+        builder = builder
+            .header("content-type", "application/x-www-form-urlencoded")
+            .map_err(CurlError::Header)?;
+    }
+    builder
+        .method_and_body(method, body)
+        .map_err(CurlError::Body)
+}
+
+/// Splits `--flag=value` and `-Xvalue` into the flag and its attached value
+fn split_flag(word: &str) -> (&str, Option<String>) {
+    if let Some(long) = word.strip_prefix("--") {
+        if let Some((name, value)) = long.split_once('=') {
+            return (&word[..name.len() + 2], Some(value.to_string()));
+        }
+    } else if word.len() > 2 && word.starts_with(['-']) && "XHdu".contains(&word[1..2]) {
+        return (&word[..2], Some(word[2..].to_string()));
+    }
+    (word, None)
+}
+
+/// Splits a command into words the way a POSIX shell would, without expanding anything
+fn split_words(command: &str) -> Result<Vec<String>, CurlError> {
+    let mut words = Vec::new();
+    let mut word = String::new();
+    let mut in_word = false;
+    let mut chars = command.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\'' => {
+                in_word = true;
+                loop {
+                    match chars.next() {
+                        Some('\'') => break,
+                        Some(c) => word.push(c),
+                        None => return Err(CurlError::Syntax("unterminated single quote")),
+                    }
+                }
+            }
+            '"' => {
+                in_word = true;
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => match chars.next() {
+                            Some(c @ ('"' | '\\' | '$' | '`')) => word.push(c),
+                            Some('\n') => {}
+                            Some(c) => word.extend(['\\', c]),
+                            None => return Err(CurlError::Syntax("unterminated double quote")),
+                        },
+                        Some(c) => word.push(c),
+                        None => return Err(CurlError::Syntax("unterminated double quote")),
+                    }
+                }
+            }
+            '\\' => match chars.next() {
+                // A line continuation
+                Some('\n') => {}
+                Some(c) => {
+                    in_word = true;
+                    word.push(c);
+                }
+                None => return Err(CurlError::Syntax("trailing backslash")),
+            },
+            c if c.is_whitespace() => {
+                if in_word {
+                    words.push(std::mem::take(&mut word));
+                    in_word = false;
+                }
+            }
+            c => {
+                in_word = true;
+                word.push(c);
+            }
+        }
+    }
+    if in_word {
+        words.push(word);
+    }
+    Ok(words)
+}
@@ -0,0 +1,388 @@
+//! Importing and exporting requests as HAR (HTTP Archive) entries
+
+use std::fmt;
+use std::io;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+
+use crate::body::Payload;
+use crate::builder::RequestBuilder;
+use crate::header::InvalidHeader;
+use crate::http1::host_header;
+use crate::method::{BodyRuleError, Method};
+use crate::request::Request;
+use crate::state::{Checked, Url};
+use crate::url::{form_urlencode, ParseError};
+
+/// A HAR file, as saved by a browser's network panel
+///
+/// ```
+/// use typestate_test::har::Har;
+///
+/// let har = Har::from_json(r#"{"log": {"version": "1.2",
+///     "creator": {"name": "Firefox", "version": "131.0"},
+///     "entries": [{"request": {
+///         "method": "POST",
+///         "url": "https://example.com/login",
+///         "headers": [{"name": "Content-Type", "value": "application/x-www-form-urlencoded"}],
+///         "postData": {"mimeType": "application/x-www-form-urlencoded", "text": "user=alice"}
+///     }}]}}"#)?;
+///
+/// let requests = har.requests()?;
+/// assert_eq!(requests[0].body().and_then(|body| body.as_str()), Some("user=alice"));
+///
+/// // And back again, e.g. to attach to a bug report
+/// let json = Har::from_requests(&requests)?.to_json();
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Har {
+    pub log: Log,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Log {
+    pub version: String,
+    pub creator: Creator,
+    #[serde(default)]
+    pub entries: Vec<Entry>,
+}
+
+/// The application that wrote the file
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Creator {
+    pub name: String,
+    pub version: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Entry {
+    pub request: HarRequest,
+    /// Everything else about the entry, such as the response and timings, kept as it was
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+/// The `request` of a HAR entry
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HarRequest {
+    pub method: String,
+    pub url: String,
+    #[serde(default = "http_1_1")]
+    pub http_version: String,
+    /// Ignored on import, since the `Cookie` header carries the same values
+    #[serde(default)]
+    pub cookies: Vec<Value>,
+    #[serde(default)]
+    pub headers: Vec<NameValue>,
+    /// Ignored on import, since the URL carries the same values
+    #[serde(default)]
+    pub query_string: Vec<NameValue>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub post_data: Option<PostData>,
+    #[serde(default = "unknown_size")]
+    pub headers_size: i64,
+    #[serde(default = "unknown_size")]
+    pub body_size: i64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NameValue {
+    pub name: String,
+    pub value: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PostData {
+    pub mime_type: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub text: Option<String>,
+    /// Form fields, which some tools write instead of `text`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub params: Vec<Param>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Param {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub value: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub file_name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content_type: Option<String>,
+}
+
+fn http_1_1() -> String {
+    "HTTP/1.1".to_string()
+}
+
+fn unknown_size() -> i64 {
+    -1
+}
+
+/// A HAR entry couldn't be turned into a request, or a request into an entry
+#[derive(Debug)]
+pub enum HarError {
+    /// The file isn't valid HAR
+    Json(serde_json::Error),
+    /// A file body couldn't be read
+    Io(io::Error),
+    /// The body is a stream or isn't valid UTF-8, so it can't be stored as `postData.text`
+    UnrepresentableBody,
+    /// `postData` has only multipart `params`, whose file contents HAR doesn't keep
+    Unsupported(&'static str),
+    /// `method` isn't a valid method token
+    InvalidMethod(String),
+    Url(ParseError),
+    Header(InvalidHeader),
+    /// The entry breaks its method's body rule, e.g. a GET with `postData`
+    Body(BodyRuleError),
+    /// The entry at `index` in `log.entries` is invalid
+    Entry {
+        index: usize,
+        source: Box<HarError>,
+    },
+}
+
+impl fmt::Display for HarError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HarError::Json(err) => write!(f, "invalid HAR: {err}"),
+            HarError::Io(err) => write!(f, "couldn't read body file: {err}"),
+            HarError::UnrepresentableBody => f.write_str("body can't be stored in a HAR entry"),
+            HarError::Unsupported(reason) => write!(f, "unsupported HAR entry: {reason}"),
+            HarError::InvalidMethod(method) => write!(f, "invalid method {method:?}"),
+            HarError::Url(err) => write!(f, "invalid URL: {err}"),
+            HarError::Header(err) => err.fmt(f),
+            HarError::Body(err) => err.fmt(f),
+            HarError::Entry { index, source } => write!(f, "HAR entry {index}: {source}"),
+        }
+    }
+}
+
+impl std::error::Error for HarError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            HarError::Json(err) => Some(err),
+            HarError::Io(err) => Some(err),
+            HarError::Url(err) => Some(err),
+            HarError::Header(err) => Some(err),
+            HarError::Body(err) => Some(err),
+            HarError::Entry { source, .. } => Some(source),
+            HarError::UnrepresentableBody
+            | HarError::Unsupported(_)
+            | HarError::InvalidMethod(_) => None,
+        }
+    }
+}
+
+impl Har {
+    pub fn from_json(json: &str) -> Result<Self, HarError> {
+        serde_json::from_str(json).map_err(HarError::Json)
+    }
+
+    /// Pretty-printed JSON
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("HAR always serializes")
+    }
+
+    /// A log with an entry for each request; nothing has been sent, so responses are empty
+    pub fn from_requests<'a>(
+        requests: impl IntoIterator<Item = &'a Request>,
+    ) -> Result<Self, HarError> {
+        let Value::Object(placeholder) = json!({
+            "startedDateTime": iso8601(SystemTime::now()),
+            "time": 0,
+            "response": {
+                "status": 0,
+                "statusText": "",
+                "httpVersion": "",
+                "cookies": [],
+                "headers": [],
+                "content": {"size": 0, "mimeType": ""},
+                "redirectURL": "",
+                "headersSize": -1,
+                "bodySize": -1,
+            },
+            "cache": {},
+            "timings": {"send": 0, "wait": 0, "receive": 0},
+        }) else {
+            unreachable!("an object literal")
+        };
+        let entries = requests
+            .into_iter()
+            .map(|request| {
+                Ok(Entry {
+                    request: request.to_har()?,
+                    extra: placeholder.clone(),
+                })
+            })
+            .collect::<Result<_, HarError>>()?;
+        Ok(Har {
+            log: Log {
+                version: "1.2".to_string(),
+                creator: Creator {
+                    name: env!("CARGO_PKG_NAME").to_string(),
+                    version: env!("CARGO_PKG_VERSION").to_string(),
+                },
+                entries,
+            },
+        })
+    }
+
+    /// Builds a request from every entry, failing on the first one that is invalid
+    pub fn requests(&self) -> Result<Vec<Request>, HarError> {
+        self.log
+            .entries
+            .iter()
+            .enumerate()
+            .map(|(index, entry)| {
+                RequestBuilder::from_har(&entry.request)
+                    .map(|builder| builder.build())
+                    .map_err(|err| HarError::Entry {
+                        index,
+                        source: Box::new(err),
+                    })
+            })
+            .collect()
+    }
+}
+
+pub(crate) fn to_har(request: &Request) -> Result<HarRequest, HarError> {
+    let text = match &request.body {
+        None => None,
+        Some(body) => {
+            let bytes = match (body.as_bytes(), body.path()) {
+                (Some(bytes), _) => bytes.to_vec(),
+                (None, Some(path)) => std::fs::read(path).map_err(HarError::Io)?,
+                (None, None) => return Err(HarError::UnrepresentableBody),
+            };
+            Some(String::from_utf8(bytes).map_err(|_| HarError::UnrepresentableBody)?)
+        }
+    };
+    let query_string = request
+        .url
+        .query()
+        .and_then(|query| serde_urlencoded::from_str::<Vec<(String, String)>>(query).ok())
+        .unwrap_or_default();
+    let mime_type = request
+        .headers
+        .get("content-type")
+        .map(|value| value.as_str().to_string())
+        .unwrap_or_default();
+    Ok(HarRequest {
+        method: request.method.as_str().to_string(),
+        url: request.url.to_string(),
+        http_version: http_1_1(),
+        cookies: Vec::new(),
+        headers: request
+            .headers
+            .iter()
+            .map(|(name, value)| NameValue {
+                name: name.to_string(),
+                value: value.to_string(),
+            })
+            .collect(),
+        query_string: query_string
+            .into_iter()
+            .map(|(name, value)| NameValue { name, value })
+            .collect(),
+        headers_size: unknown_size(),
+        body_size: text.as_ref().map_or(0, |text| text.len() as i64),
+        post_data: text.map(|text| PostData {
+            mime_type,
+            text: Some(text),
+            params: Vec::new(),
+        }),
+    })
+}
+
+pub(crate) fn from_har(har: &HarRequest) -> Result<RequestBuilder<Url, Method, Checked>, HarError> {
+    let method =
+        Method::from_token(&har.method).map_err(|_| HarError::InvalidMethod(har.method.clone()))?;
+    let url = crate::Url::parse(&har.url).map_err(HarError::Url)?;
+    let derived_host = host_header(&url);
+    let mut builder = RequestBuilder::new().url(url).map_err(HarError::Url)?;
+    for NameValue { name, value } in &har.headers {
+        // HTTP/2 pseudo-headers repeat the method and URL, and `Content-Length` and a matching
+        // `Host` are derived again when the request is sent
+        let derived = name.starts_with(':')
+            || name.eq_ignore_ascii_case("content-length")
+            || (name.eq_ignore_ascii_case("host") && *value == derived_host);
+        if !derived {
+            builder = builder
+                .header(name.as_str(), value.as_str())
+                .map_err(HarError::Header)?;
+        }
+    }
+
+    let body = match &har.post_data {
+        None => None,
+        Some(post) => {
+            let text = match (&post.text, post.params.as_slice()) {
+                (Some(text), _) => text.clone(),
+                (None, []) => String::new(),
+                (None, params)
+                    if post
+                        .mime_type
+                        .starts_with("application/x-www-form-urlencoded") =>
+                {
+                    params
+                        .iter()
+                        .map(|param| {
+                            let value = param.value.as_deref().unwrap_or_default();
+                            format!("{}={}", form_urlencode(&param.name), form_urlencode(value))
+                        })
+                        .collect::<Vec<_>>()
+                        .join("&")
+                }
+                (None, _) => {
+                    return Err(HarError::Unsupported(
+                        "multipart `params` without the body `text`",
+                    ))
+                }
+            };
+            if !post.mime_type.is_empty() && !builder.headers().contains_key("content-type") {
+                builder = builder
+                    .header("content-type", post.mime_type.as_str())
+                    .map_err(HarError::Header)?;
+            }
+            Some(Payload::from(text))
+        }
+    };
+    builder
+        .method_and_body(method, body)
+        .map_err(HarError::Body)
+}
+
+/// Formats `time` as an ISO 8601 UTC timestamp with milliseconds, as `startedDateTime` expects
+fn iso8601(time: SystemTime) -> String {
+    let since_epoch = time.duration_since(UNIX_EPOCH).unwrap_or_default();
+    let secs = since_epoch.as_secs();
+    let (days, secs_of_day) = (secs / 86_400, secs % 86_400);
+
+    // Days since 1970-01-01 to a civil date, from Howard Hinnant's `civil_from_days`
+    let z = days + 719_468;
+    let era = z / 146_097;
+    let doe = z % 146_097;
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + u64::from(month <= 2);
+
+    format!(
+        "{year:04}-{month:02}-{day:02}T{:02}:{:02}:{:02}.{:03}Z",
+        secs_of_day / 3_600,
+        secs_of_day / 60 % 60,
+        secs_of_day % 60,
+        since_epoch.subsec_millis()
+    )
+}
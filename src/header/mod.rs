@@ -0,0 +1,427 @@
+use std::borrow::{Borrow, Cow};
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::fmt;
+
+mod typed;
+
+#[cfg(feature = "json")]
+pub(crate) use typed::{base64_decode, base64_encode};
+pub use typed::{
+    Accept, Authorization, ByteRange, ContentLength, ContentType, ETag, IfNoneMatch,
+    InvalidTypedHeader, Range, TypedHeader, UserAgent, ACCEPT, AUTHORIZATION, CONTENT_LENGTH,
+    CONTENT_TYPE, HOST, IF_NONE_MATCH, RANGE, USER_AGENT,
+};
+
+/// A validated, lowercased header name
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct HeaderName(Cow<'static, str>);
+
+/// A validated header value, which can never contain CR, LF or other control characters
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct HeaderValue(Cow<'static, str>);
+
+/// The name is empty or contains a character that isn't allowed in a header name
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InvalidHeaderName(String);
+
+/// The value contains a control character such as CR or LF
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InvalidHeaderValue(String);
+
+/// Either half of a header was invalid
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum InvalidHeader {
+    Name(InvalidHeaderName),
+    Value(InvalidHeaderValue),
+}
+
+/// `remove_header()` was asked to remove `Content-Type`, which a body depends on
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProtectedHeader(pub(crate) HeaderName);
+
+impl fmt::Display for InvalidHeaderName {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid header name {:?}", self.0)
+    }
+}
+
+impl fmt::Display for InvalidHeaderValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid header value {:?}", self.0)
+    }
+}
+
+impl fmt::Display for InvalidHeader {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            InvalidHeader::Name(err) => err.fmt(f),
+            InvalidHeader::Value(err) => err.fmt(f),
+        }
+    }
+}
+
+impl fmt::Display for ProtectedHeader {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "`{}` can only be set with `content_type()`", self.0)
+    }
+}
+
+impl std::error::Error for InvalidHeaderName {}
+impl std::error::Error for ProtectedHeader {}
+impl std::error::Error for InvalidHeaderValue {}
+impl std::error::Error for InvalidHeader {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            InvalidHeader::Name(err) => Some(err),
+            InvalidHeader::Value(err) => Some(err),
+        }
+    }
+}
+
+impl From<InvalidHeaderName> for InvalidHeader {
+    fn from(err: InvalidHeaderName) -> Self {
+        InvalidHeader::Name(err)
+    }
+}
+
+impl From<InvalidHeaderValue> for InvalidHeader {
+    fn from(err: InvalidHeaderValue) -> Self {
+        InvalidHeader::Value(err)
+    }
+}
+
+// Lets `header()` take an already validated `HeaderName`/`HeaderValue`
+impl From<Infallible> for InvalidHeader {
+    fn from(err: Infallible) -> Self {
+        match err {}
+    }
+}
+
+impl From<Infallible> for InvalidHeaderValue {
+    fn from(err: Infallible) -> Self {
+        match err {}
+    }
+}
+
+pub(crate) const fn is_tchar(b: u8) -> bool {
+    // `#`..=`'` covers `#$%&'`
+    b.is_ascii_alphanumeric()
+        || matches!(
+            b,
+            b'!' | b'#'..=b'\'' | b'*' | b'+' | b'-' | b'.' | b'^' | b'_' | b'`' | b'|' | b'~'
+        )
+}
+
+pub(crate) const fn is_value_byte(b: u8) -> bool {
+    b == b'\t' || (b >= 0x20 && b != 0x7f)
+}
+
+impl HeaderName {
+    /// Returns a HeaderName for a literal, panicking if it isn't a valid lowercase name
+    pub const fn from_static(name: &'static str) -> Self {
+        let bytes = name.as_bytes();
+        assert!(!bytes.is_empty(), "header name is empty");
+        let mut i = 0;
+        while i < bytes.len() {
+            assert!(
+                is_tchar(bytes[i]) && !bytes[i].is_ascii_uppercase(),
+                "header name must be lowercase and only contain token characters"
+            );
+            i += 1;
+        }
+        HeaderName(Cow::Borrowed(name))
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl HeaderValue {
+    /// Returns a HeaderValue for a literal, panicking if it contains a control character
+    pub const fn from_static(value: &'static str) -> Self {
+        let bytes = value.as_bytes();
+        let mut i = 0;
+        while i < bytes.len() {
+            assert!(
+                is_value_byte(bytes[i]),
+                "header value contains a control character"
+            );
+            i += 1;
+        }
+        HeaderValue(Cow::Borrowed(value))
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl TryFrom<&str> for HeaderName {
+    type Error = InvalidHeaderName;
+
+    fn try_from(name: &str) -> Result<Self, Self::Error> {
+        if name.is_empty() || !name.bytes().all(is_tchar) {
+            return Err(InvalidHeaderName(name.to_string()));
+        }
+        Ok(HeaderName(Cow::Owned(name.to_ascii_lowercase())))
+    }
+}
+
+impl TryFrom<String> for HeaderName {
+    type Error = InvalidHeaderName;
+
+    fn try_from(name: String) -> Result<Self, Self::Error> {
+        HeaderName::try_from(name.as_str())
+    }
+}
+
+impl TryFrom<&String> for HeaderName {
+    type Error = InvalidHeaderName;
+
+    fn try_from(name: &String) -> Result<Self, Self::Error> {
+        HeaderName::try_from(name.as_str())
+    }
+}
+
+impl TryFrom<&str> for HeaderValue {
+    type Error = InvalidHeaderValue;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        HeaderValue::try_from(value.to_string())
+    }
+}
+
+impl TryFrom<String> for HeaderValue {
+    type Error = InvalidHeaderValue;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        if !value.bytes().all(is_value_byte) {
+            return Err(InvalidHeaderValue(value));
+        }
+        Ok(HeaderValue(Cow::Owned(value)))
+    }
+}
+
+impl TryFrom<&String> for HeaderValue {
+    type Error = InvalidHeaderValue;
+
+    fn try_from(value: &String) -> Result<Self, Self::Error> {
+        HeaderValue::try_from(value.clone())
+    }
+}
+
+impl Borrow<str> for HeaderName {
+    fn borrow(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Display for HeaderName {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl fmt::Display for HeaderValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+/// Request headers with case-insensitive names and multiple values per name
+///
+/// Names keep the order they were first inserted in, and values keep the order they were
+/// appended in.
+///
+/// ```
+/// use typestate_test::header::{HeaderMap, HeaderName, HeaderValue};
+///
+/// let mut headers = HeaderMap::new();
+/// headers.append(HeaderName::try_from("Accept")?, HeaderValue::try_from("text/html")?);
+/// headers.append(HeaderName::try_from("accept")?, HeaderValue::try_from("application/json")?);
+///
+/// assert_eq!(headers.get_all("ACCEPT").len(), 2);
+/// assert!(HeaderValue::try_from("x\r\nInjected: yes").is_err());
+/// # Ok::<(), typestate_test::header::InvalidHeader>(())
+/// ```
+#[derive(Clone, Default, PartialEq, Eq)]
+pub struct HeaderMap {
+    entries: Vec<(HeaderName, Vec<HeaderValue>)>,
+    index: HashMap<HeaderName, usize>,
+}
+
+impl HeaderMap {
+    pub fn new() -> Self {
+        HeaderMap::default()
+    }
+
+    /// Sets `name` to `value`, replacing and returning any values it already had
+    pub fn insert(&mut self, name: HeaderName, value: HeaderValue) -> Vec<HeaderValue> {
+        match self.index.get(&name) {
+            Some(&i) => std::mem::replace(&mut self.entries[i].1, vec![value]),
+            None => {
+                self.push_entry(name, value);
+                Vec::new()
+            }
+        }
+    }
+
+    /// Adds `value` to `name`, keeping any values it already had
+    pub fn append(&mut self, name: HeaderName, value: HeaderValue) {
+        match self.index.get(&name) {
+            Some(&i) => self.entries[i].1.push(value),
+            None => self.push_entry(name, value),
+        }
+    }
+
+    /// Removes and returns every value of `name`
+    pub fn remove(&mut self, name: &str) -> Vec<HeaderValue> {
+        let Some(i) = self.index.remove(name.to_ascii_lowercase().as_str()) else {
+            return Vec::new();
+        };
+        let (_, values) = self.entries.remove(i);
+        for index in self.index.values_mut() {
+            if *index > i {
+                *index -= 1;
+            }
+        }
+        values
+    }
+
+    /// The first value of `name`
+    pub fn get(&self, name: &str) -> Option<&HeaderValue> {
+        self.get_all(name).first()
+    }
+
+    /// Every value of `name`, in the order they were added
+    pub fn get_all(&self, name: &str) -> &[HeaderValue] {
+        match self.index.get(name.to_ascii_lowercase().as_str()) {
+            Some(&i) => &self.entries[i].1,
+            None => &[],
+        }
+    }
+
+    /// Sets the typed header `H`, replacing any values it already had
+    pub fn typed_insert<H: TypedHeader>(&mut self, header: &H) {
+        self.insert(H::NAME, header.encode());
+    }
+
+    /// Decodes the typed header `H`, or returns `None` if it isn't set
+    pub fn typed_get<H: TypedHeader>(&self) -> Result<Option<H>, InvalidTypedHeader> {
+        match self.get_all(H::NAME.as_str()) {
+            [] => Ok(None),
+            values => H::decode(values).map(Some),
+        }
+    }
+
+    pub fn contains_key(&self, name: &str) -> bool {
+        self.index.contains_key(name.to_ascii_lowercase().as_str())
+    }
+
+    /// The number of values, counting every value of a repeated name
+    pub fn len(&self) -> usize {
+        self.entries.iter().map(|(_, values)| values.len()).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Every `(name, value)` pair, with repeated names yielded once per value
+    pub fn iter(&self) -> impl Iterator<Item = (&HeaderName, &HeaderValue)> {
+        self.entries
+            .iter()
+            .flat_map(|(name, values)| values.iter().map(move |value| (name, value)))
+    }
+
+    fn push_entry(&mut self, name: HeaderName, value: HeaderValue) {
+        self.index.insert(name.clone(), self.entries.len());
+        self.entries.push((name, vec![value]));
+    }
+}
+
+impl fmt::Debug for HeaderMap {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map()
+            .entries(
+                self.iter()
+                    .map(|(name, value)| (name.as_str(), value.as_str())),
+            )
+            .finish()
+    }
+}
+
+impl<'a> IntoIterator for &'a HeaderMap {
+    type Item = (&'a HeaderName, &'a HeaderValue);
+    type IntoIter = Box<dyn Iterator<Item = Self::Item> + 'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        Box::new(self.iter())
+    }
+}
+
+impl Extend<(HeaderName, HeaderValue)> for HeaderMap {
+    fn extend<I: IntoIterator<Item = (HeaderName, HeaderValue)>>(&mut self, iter: I) {
+        for (name, value) in iter {
+            self.append(name, value);
+        }
+    }
+}
+
+impl FromIterator<(HeaderName, HeaderValue)> for HeaderMap {
+    fn from_iter<I: IntoIterator<Item = (HeaderName, HeaderValue)>>(iter: I) -> Self {
+        let mut headers = HeaderMap::new();
+        headers.extend(iter);
+        headers
+    }
+}
+
+// Names and values are stored as strings and validated again when read back
+#[cfg(feature = "serde")]
+impl serde::Serialize for HeaderName {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for HeaderName {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let name = <String as serde::Deserialize>::deserialize(deserializer)?;
+        HeaderName::try_from(name).map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for HeaderValue {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for HeaderValue {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let value = <String as serde::Deserialize>::deserialize(deserializer)?;
+        HeaderValue::try_from(value).map_err(serde::de::Error::custom)
+    }
+}
+
+/// A sequence of `[name, value]` pairs, so repeated names keep their values and order
+#[cfg(feature = "serde")]
+impl serde::Serialize for HeaderMap {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.iter())
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for HeaderMap {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let pairs =
+            <Vec<(HeaderName, HeaderValue)> as serde::Deserialize>::deserialize(deserializer)?;
+        Ok(pairs.into_iter().collect())
+    }
+}
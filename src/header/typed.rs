@@ -0,0 +1,455 @@
+use std::fmt;
+
+use super::{HeaderName, HeaderValue, InvalidHeaderValue};
+use crate::mime::{split_quoted, Mime};
+
+pub const ACCEPT: HeaderName = HeaderName::from_static("accept");
+pub const AUTHORIZATION: HeaderName = HeaderName::from_static("authorization");
+pub const CONTENT_LENGTH: HeaderName = HeaderName::from_static("content-length");
+pub const CONTENT_TYPE: HeaderName = HeaderName::from_static("content-type");
+pub const HOST: HeaderName = HeaderName::from_static("host");
+pub const IF_NONE_MATCH: HeaderName = HeaderName::from_static("if-none-match");
+pub const RANGE: HeaderName = HeaderName::from_static("range");
+pub const USER_AGENT: HeaderName = HeaderName::from_static("user-agent");
+
+/// A header with a fixed name and a structured value
+pub trait TypedHeader: Sized {
+    /// The header this type is sent as
+    const NAME: HeaderName;
+
+    /// Encodes the value, which was validated when `self` was created
+    fn encode(&self) -> HeaderValue;
+
+    /// Decodes every value sent for `NAME`, in order
+    fn decode(values: &[HeaderValue]) -> Result<Self, InvalidTypedHeader>;
+}
+
+/// A header value couldn't be decoded into its typed form
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InvalidTypedHeader(pub(crate) HeaderName);
+
+impl fmt::Display for InvalidTypedHeader {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "malformed `{}` header", self.0)
+    }
+}
+
+impl std::error::Error for InvalidTypedHeader {}
+
+/// Returns the only value of a header that can't be repeated
+fn single<H: TypedHeader>(values: &[HeaderValue]) -> Result<&str, InvalidTypedHeader> {
+    match values {
+        [value] => Ok(value.as_str()),
+        _ => Err(InvalidTypedHeader(H::NAME)),
+    }
+}
+
+/// `Content-Type`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ContentType(pub Mime);
+
+impl TypedHeader for ContentType {
+    const NAME: HeaderName = CONTENT_TYPE;
+
+    fn encode(&self) -> HeaderValue {
+        encode_display(&self.0)
+    }
+
+    fn decode(values: &[HeaderValue]) -> Result<Self, InvalidTypedHeader> {
+        let mime =
+            Mime::parse(single::<Self>(values)?).map_err(|_| InvalidTypedHeader(Self::NAME))?;
+        Ok(ContentType(mime))
+    }
+}
+
+/// `Accept`, with any `q` weights kept as media type parameters
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Accept(pub Vec<Mime>);
+
+impl TypedHeader for Accept {
+    const NAME: HeaderName = ACCEPT;
+
+    fn encode(&self) -> HeaderValue {
+        let types: Vec<String> = self.0.iter().map(Mime::to_string).collect();
+        encode_display(&types.join(", "))
+    }
+
+    fn decode(values: &[HeaderValue]) -> Result<Self, InvalidTypedHeader> {
+        let mut types = Vec::new();
+        for value in values {
+            for item in split_quoted(value.as_str(), ',') {
+                if item.trim().is_empty() {
+                    continue;
+                }
+                types.push(Mime::parse(item.trim()).map_err(|_| InvalidTypedHeader(Self::NAME))?);
+            }
+        }
+        Ok(Accept(types))
+    }
+}
+
+/// `Authorization` with the `Bearer` or `Basic` scheme
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Authorization(Credentials);
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Credentials {
+    Bearer(String),
+    Basic { username: String, password: String },
+}
+
+impl Authorization {
+    /// `Bearer <token>`, or an error if `token` isn't a valid token68
+    pub fn bearer(token: impl Into<String>) -> Result<Self, InvalidHeaderValue> {
+        let token = token.into();
+        let body = token.trim_end_matches('=');
+        let valid = !body.is_empty()
+            && body.bytes().all(|b| {
+                b.is_ascii_alphanumeric() || matches!(b, b'-' | b'.' | b'_' | b'~' | b'+' | b'/')
+            });
+        if !valid {
+            return Err(InvalidHeaderValue(token));
+        }
+        Ok(Authorization(Credentials::Bearer(token)))
+    }
+
+    /// `Basic <base64(username:password)>`, or an error if `username` contains a `:`
+    ///
+    /// The first `:` separates the two once encoded, so such a username would be split apart.
+    ///
+    /// ```
+    /// use typestate_test::header::Authorization;
+    ///
+    /// assert!(Authorization::basic("aladdin", "open:sesame").is_ok());
+    /// assert!(Authorization::basic("ala:ddin", "opensesame").is_err());
+    /// ```
+    pub fn basic(
+        username: impl Into<String>,
+        password: impl Into<String>,
+    ) -> Result<Self, InvalidHeaderValue> {
+        let username = username.into();
+        if username.contains(':') {
+            return Err(InvalidHeaderValue(username));
+        }
+        Ok(Authorization(Credentials::Basic {
+            username,
+            password: password.into(),
+        }))
+    }
+
+    /// The bearer token, if this uses the `Bearer` scheme
+    pub fn token(&self) -> Option<&str> {
+        match &self.0 {
+            Credentials::Bearer(token) => Some(token),
+            Credentials::Basic { .. } => None,
+        }
+    }
+
+    /// The username and password, if this uses the `Basic` scheme
+    pub fn username_password(&self) -> Option<(&str, &str)> {
+        match &self.0 {
+            Credentials::Basic { username, password } => Some((username, password)),
+            Credentials::Bearer(_) => None,
+        }
+    }
+}
+
+impl TypedHeader for Authorization {
+    const NAME: HeaderName = AUTHORIZATION;
+
+    fn encode(&self) -> HeaderValue {
+        match &self.0 {
+            Credentials::Bearer(token) => encode_display(&format!("Bearer {token}")),
+            Credentials::Basic { username, password } => {
+                let credentials = base64_encode(format!("{username}:{password}").as_bytes());
+                encode_display(&format!("Basic {credentials}"))
+            }
+        }
+    }
+
+    fn decode(values: &[HeaderValue]) -> Result<Self, InvalidTypedHeader> {
+        let invalid = || InvalidTypedHeader(Self::NAME);
+        let (scheme, credentials) = single::<Self>(values)?
+            .split_once(' ')
+            .ok_or_else(invalid)?;
+        let credentials = credentials.trim();
+        if scheme.eq_ignore_ascii_case("bearer") {
+            Authorization::bearer(credentials).map_err(|_| invalid())
+        } else if scheme.eq_ignore_ascii_case("basic") {
+            let decoded = base64_decode(credentials).ok_or_else(invalid)?;
+            let decoded = String::from_utf8(decoded).map_err(|_| invalid())?;
+            let (username, password) = decoded.split_once(':').ok_or_else(invalid)?;
+            Authorization::basic(username, password).map_err(|_| invalid())
+        } else {
+            Err(invalid())
+        }
+    }
+}
+
+/// `User-Agent`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UserAgent(pub HeaderValue);
+
+impl TypedHeader for UserAgent {
+    const NAME: HeaderName = USER_AGENT;
+
+    fn encode(&self) -> HeaderValue {
+        self.0.clone()
+    }
+
+    fn decode(values: &[HeaderValue]) -> Result<Self, InvalidTypedHeader> {
+        match values {
+            [value] => Ok(UserAgent(value.clone())),
+            _ => Err(InvalidTypedHeader(Self::NAME)),
+        }
+    }
+}
+
+/// `Content-Length`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ContentLength(pub u64);
+
+impl TypedHeader for ContentLength {
+    const NAME: HeaderName = CONTENT_LENGTH;
+
+    fn encode(&self) -> HeaderValue {
+        encode_display(&self.0)
+    }
+
+    fn decode(values: &[HeaderValue]) -> Result<Self, InvalidTypedHeader> {
+        let value = single::<Self>(values)?;
+        // `parse` alone would accept a leading `+`
+        if !value.bytes().all(|b| b.is_ascii_digit()) {
+            return Err(InvalidTypedHeader(Self::NAME));
+        }
+        value
+            .parse()
+            .map(ContentLength)
+            .map_err(|_| InvalidTypedHeader(Self::NAME))
+    }
+}
+
+/// An entity tag, e.g. `"v1"` or `W/"v1"`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ETag {
+    tag: String,
+    weak: bool,
+}
+
+impl ETag {
+    /// A strong tag, or an error if `tag` contains a `"` or a control character
+    pub fn strong(tag: impl Into<String>) -> Result<Self, InvalidHeaderValue> {
+        ETag::new(tag.into(), false)
+    }
+
+    /// A weak tag, or an error if `tag` contains a `"` or a control character
+    pub fn weak(tag: impl Into<String>) -> Result<Self, InvalidHeaderValue> {
+        ETag::new(tag.into(), true)
+    }
+
+    fn new(tag: String, weak: bool) -> Result<Self, InvalidHeaderValue> {
+        if !tag
+            .bytes()
+            .all(|b| b == 0x21 || (0x23..0x7f).contains(&b) || b >= 0x80)
+        {
+            return Err(InvalidHeaderValue(tag));
+        }
+        Ok(ETag { tag, weak })
+    }
+
+    /// The tag without its quotes
+    pub fn tag(&self) -> &str {
+        &self.tag
+    }
+
+    pub fn is_weak(&self) -> bool {
+        self.weak
+    }
+
+    fn parse(s: &str) -> Option<Self> {
+        let (weak, quoted) = match s.strip_prefix("W/") {
+            Some(quoted) => (true, quoted),
+            None => (false, s),
+        };
+        let tag = quoted.strip_prefix('"')?.strip_suffix('"')?;
+        ETag::new(tag.to_string(), weak).ok()
+    }
+}
+
+impl fmt::Display for ETag {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.weak {
+            f.write_str("W/")?;
+        }
+        write!(f, "\"{}\"", self.tag)
+    }
+}
+
+/// `If-None-Match`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum IfNoneMatch {
+    /// `*`, matching any current representation
+    Any,
+    Tags(Vec<ETag>),
+}
+
+impl TypedHeader for IfNoneMatch {
+    const NAME: HeaderName = IF_NONE_MATCH;
+
+    fn encode(&self) -> HeaderValue {
+        match self {
+            IfNoneMatch::Any => HeaderValue::from_static("*"),
+            IfNoneMatch::Tags(tags) => {
+                let tags: Vec<String> = tags.iter().map(ETag::to_string).collect();
+                encode_display(&tags.join(", "))
+            }
+        }
+    }
+
+    fn decode(values: &[HeaderValue]) -> Result<Self, InvalidTypedHeader> {
+        if let [value] = values {
+            if value.as_str().trim() == "*" {
+                return Ok(IfNoneMatch::Any);
+            }
+        }
+        let mut tags = Vec::new();
+        for value in values {
+            for tag in split_quoted(value.as_str(), ',') {
+                let tag = ETag::parse(tag.trim()).ok_or(InvalidTypedHeader(Self::NAME))?;
+                tags.push(tag);
+            }
+        }
+        Ok(IfNoneMatch::Tags(tags))
+    }
+}
+
+/// One range of a `Range: bytes=...` header
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ByteRange {
+    /// `first-last`, both inclusive
+    FromTo(u64, u64),
+    /// `first-`, up to the end
+    From(u64),
+    /// `-n`, the last `n` bytes
+    Last(u64),
+}
+
+/// `Range` in bytes, e.g. `bytes=0-99, 200-`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Range(Vec<ByteRange>);
+
+impl Range {
+    /// The given ranges, or an error if there are none or a `FromTo` ends before it starts
+    ///
+    /// ```
+    /// use typestate_test::header::{ByteRange, Range};
+    ///
+    /// assert!(Range::new([ByteRange::FromTo(0, 99), ByteRange::Last(10)]).is_ok());
+    /// assert!(Range::new([ByteRange::FromTo(5, 1)]).is_err());
+    /// assert!(Range::new([]).is_err());
+    /// ```
+    pub fn new(ranges: impl Into<Vec<ByteRange>>) -> Result<Self, InvalidTypedHeader> {
+        let ranges = ranges.into();
+        let backwards =
+            |range: &ByteRange| matches!(range, ByteRange::FromTo(first, last) if first > last);
+        if ranges.is_empty() || ranges.iter().any(backwards) {
+            return Err(InvalidTypedHeader(Self::NAME));
+        }
+        Ok(Range(ranges))
+    }
+
+    pub fn ranges(&self) -> &[ByteRange] {
+        &self.0
+    }
+}
+
+impl TypedHeader for Range {
+    const NAME: HeaderName = RANGE;
+
+    fn encode(&self) -> HeaderValue {
+        let ranges: Vec<String> = self
+            .0
+            .iter()
+            .map(|range| match range {
+                ByteRange::FromTo(first, last) => format!("{first}-{last}"),
+                ByteRange::From(first) => format!("{first}-"),
+                ByteRange::Last(n) => format!("-{n}"),
+            })
+            .collect();
+        encode_display(&format!("bytes={}", ranges.join(", ")))
+    }
+
+    fn decode(values: &[HeaderValue]) -> Result<Self, InvalidTypedHeader> {
+        let invalid = || InvalidTypedHeader(Self::NAME);
+        let ranges = single::<Self>(values)?
+            .strip_prefix("bytes=")
+            .ok_or_else(invalid)?;
+        let number = |s: &str| -> Result<u64, InvalidTypedHeader> {
+            if s.is_empty() || !s.bytes().all(|b| b.is_ascii_digit()) {
+                return Err(invalid());
+            }
+            s.parse().map_err(|_| invalid())
+        };
+
+        let mut parsed = Vec::new();
+        for range in ranges.split(',') {
+            let (first, last) = range.trim().split_once('-').ok_or_else(invalid)?;
+            parsed.push(match (first, last) {
+                ("", n) => ByteRange::Last(number(n)?),
+                (first, "") => ByteRange::From(number(first)?),
+                (first, last) => ByteRange::FromTo(number(first)?, number(last)?),
+            });
+        }
+        Range::new(parsed)
+    }
+}
+
+/// Every typed header builds its value from validated parts, so this can't fail
+fn encode_display(value: &impl fmt::Display) -> HeaderValue {
+    HeaderValue::try_from(value.to_string()).expect("typed header values are always valid")
+}
+
+const BASE64: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+pub(crate) fn base64_encode(input: &[u8]) -> String {
+    let mut encoded = String::with_capacity(input.len().div_ceil(3) * 4);
+    for chunk in input.chunks(3) {
+        let b = [
+            chunk[0],
+            *chunk.get(1).unwrap_or(&0),
+            *chunk.get(2).unwrap_or(&0),
+        ];
+        let n = (b[0] as u32) << 16 | (b[1] as u32) << 8 | b[2] as u32;
+        for i in 0..4 {
+            if i <= chunk.len() {
+                encoded.push(BASE64[(n >> (18 - 6 * i)) as usize & 0x3f] as char);
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+    encoded
+}
+
+pub(crate) fn base64_decode(input: &str) -> Option<Vec<u8>> {
+    let unpadded = input.trim_end_matches('=');
+    let padding = input.len() - unpadded.len();
+    // One leftover character only holds 6 bits, and padding fills out a whole group of 4
+    if unpadded.len() % 4 == 1 || padding > 2 || (padding > 0 && !input.len().is_multiple_of(4)) {
+        return None;
+    }
+    let input = unpadded;
+    let mut decoded = Vec::with_capacity(input.len() * 3 / 4);
+    let mut buffer = 0u32;
+    let mut bits = 0;
+    for c in input.bytes() {
+        let value = BASE64.iter().position(|&b| b == c)? as u32;
+        buffer = buffer << 6 | value;
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            decoded.push((buffer >> bits) as u8);
+        }
+    }
+    Some(decoded)
+}
@@ -0,0 +1,731 @@
+//! A minimal HTTP/1.1 client over `std::net::TcpStream`
+//!
+//! Only plain `http` URLs are supported; there is no TLS.
+
+use std::fmt;
+use std::io::{self, BufWriter, Read, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+
+use crate::body::Payload;
+use crate::builder::RequestBuilder;
+use crate::header::{ContentLength, HeaderMap, HeaderName, HeaderValue};
+use crate::method::{BodyRule, BodyRuleError, Method};
+use crate::request::Request;
+use crate::response::Response;
+use crate::url::Url;
+
+/// The largest response head we're willing to buffer
+const MAX_HEAD_LEN: usize = 64 * 1024;
+
+/// A request couldn't be sent, or its response couldn't be read
+#[derive(Debug)]
+pub enum SendError {
+    /// Only `http` URLs can be sent
+    UnsupportedScheme(String),
+    /// Connecting, writing or reading failed, including timeouts
+    Io(io::Error),
+    /// The server sent something that isn't a valid HTTP/1.1 response
+    InvalidResponse(&'static str),
+    /// Any other failure, reported by a custom `Transport`
+    Transport(Box<dyn std::error::Error + Send + Sync>),
+}
+
+impl fmt::Display for SendError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SendError::UnsupportedScheme(scheme) => write!(f, "unsupported URL scheme `{scheme}`"),
+            SendError::Io(err) => write!(f, "connection failed: {err}"),
+            SendError::InvalidResponse(reason) => write!(f, "invalid response: {reason}"),
+            SendError::Transport(err) => err.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for SendError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            SendError::Io(err) => Some(err),
+            SendError::Transport(err) => Some(&**err),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for SendError {
+    fn from(err: io::Error) -> Self {
+        SendError::Io(err)
+    }
+}
+
+/// Sends `request` on a new connection and reads the whole response
+///
+/// The request's timeout applies to connecting and to each read and write.
+pub(crate) fn send(request: Request) -> Result<Response, SendError> {
+    let Request {
+        url,
+        method,
+        headers,
+        body,
+        timeout,
+    } = request;
+    let port = socket_port(&url)?;
+
+    let mut last_err = None;
+    let mut stream = None;
+    for addr in (unbracketed_host(&url), port).to_socket_addrs()? {
+        let connected = match timeout {
+            Some(timeout) => TcpStream::connect_timeout(&addr, timeout),
+            None => TcpStream::connect(addr),
+        };
+        match connected {
+            Ok(connected) => {
+                stream = Some(connected);
+                break;
+            }
+            Err(err) => last_err = Some(err),
+        }
+    }
+    let mut stream = match stream {
+        Some(stream) => stream,
+        None => {
+            return Err(last_err
+                .unwrap_or_else(|| io::Error::new(io::ErrorKind::NotFound, "host has no addresses"))
+                .into())
+        }
+    };
+    stream.set_read_timeout(timeout)?;
+    stream.set_write_timeout(timeout)?;
+
+    let framing = request_framing(&method, &headers, body.as_ref())?;
+    let mut writer = BufWriter::new(&mut stream);
+    let head = request_head(&origin_form(&url), &url, &method, &headers, framing, true);
+    writer.write_all(&head)?;
+    if let Some(body) = body {
+        write_body(&mut writer, body, framing)?;
+    }
+    writer.flush()?;
+    drop(writer);
+
+    read_response(&mut stream, &method)
+}
+
+/// The port to connect to, or an error for anything but `http`
+pub(crate) fn socket_port(url: &Url) -> Result<u16, SendError> {
+    match url.scheme() {
+        "http" => Ok(url.port().unwrap_or(80)),
+        scheme => Err(SendError::UnsupportedScheme(scheme.to_string())),
+    }
+}
+
+/// The host without the brackets around an IPv6 address
+pub(crate) fn unbracketed_host(url: &Url) -> &str {
+    url.host().trim_start_matches('[').trim_end_matches(']')
+}
+
+/// How the request body is delimited on the wire
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum RequestFraming {
+    /// Nothing to add; either there's no body or `Content-Length` was set by hand
+    Unframed,
+    Length(u64),
+    Chunked,
+}
+
+pub(crate) fn request_framing(
+    method: &Method,
+    headers: &HeaderMap,
+    body: Option<&Payload>,
+) -> io::Result<RequestFraming> {
+    if headers.contains_key("content-length") {
+        return Ok(RequestFraming::Unframed);
+    }
+    Ok(match body {
+        Some(body) => match body.content_length()? {
+            Some(len) => RequestFraming::Length(len),
+            None => RequestFraming::Chunked,
+        },
+        // Servers may wait for a body on these unless told there isn't one
+        None if matches!(method, Method::POST | Method::PUT | Method::PATCH) => {
+            RequestFraming::Length(0)
+        }
+        None => RequestFraming::Unframed,
+    })
+}
+
+/// The request line and headers, with `Host` and the body framing headers added unless set
+pub(crate) fn request_head(
+    target: &str,
+    url: &Url,
+    method: &Method,
+    headers: &HeaderMap,
+    framing: RequestFraming,
+    close: bool,
+) -> Vec<u8> {
+    let mut head = format!("{} {target} HTTP/1.1\r\n", method.as_str());
+    if !headers.contains_key("host") {
+        head.push_str(&format!("host: {}\r\n", host_header(url)));
+    }
+    for (name, value) in headers {
+        head.push_str(&format!("{name}: {value}\r\n"));
+    }
+    match framing {
+        RequestFraming::Unframed => {}
+        RequestFraming::Length(len) => head.push_str(&format!("content-length: {len}\r\n")),
+        RequestFraming::Chunked => head.push_str("transfer-encoding: chunked\r\n"),
+    }
+    if close && !headers.contains_key("connection") {
+        head.push_str("connection: close\r\n");
+    }
+    head.push_str("\r\n");
+    head.into_bytes()
+}
+
+/// The `Host` header derived from the URL, which includes the port only if the URL does
+pub(crate) fn host_header(url: &Url) -> String {
+    match url.port() {
+        Some(port) => format!("{}:{port}", url.host()),
+        None => url.host().to_string(),
+    }
+}
+
+/// The path and query, which is what goes in the request line
+pub(crate) fn origin_form(url: &Url) -> String {
+    match url.query() {
+        Some(query) => format!("{}?{query}", url.path()),
+        None => url.path().to_string(),
+    }
+}
+
+fn write_body(writer: &mut impl Write, body: Payload, framing: RequestFraming) -> io::Result<()> {
+    let mut reader = body.into_reader()?;
+    if framing != RequestFraming::Chunked {
+        io::copy(&mut reader, writer)?;
+        return Ok(());
+    }
+    let mut chunk = [0; 8 * 1024];
+    loop {
+        let n = reader.read(&mut chunk)?;
+        if n == 0 {
+            break;
+        }
+        write!(writer, "{n:x}\r\n")?;
+        writer.write_all(&chunk[..n])?;
+        writer.write_all(b"\r\n")?;
+    }
+    writer.write_all(b"0\r\n\r\n")
+}
+
+fn read_response(stream: &mut impl Read, method: &Method) -> Result<Response, SendError> {
+    let mut parser = ResponseParser::new(method.clone());
+    let mut chunk = [0; 8 * 1024];
+    loop {
+        if let Some(response) = parser.advance()? {
+            return Ok(response);
+        }
+        match stream.read(&mut chunk)? {
+            0 => return parser.finish(),
+            n => parser.push(&chunk[..n]),
+        }
+    }
+}
+
+/// Incrementally parses one response from bytes as they arrive, without doing any IO itself
+#[derive(Debug)]
+pub(crate) struct ResponseParser {
+    method: Method,
+    buf: Vec<u8>,
+    received: bool,
+    state: ParseState,
+}
+
+#[derive(Debug)]
+// Only the async pool reuses connections, so only it reads `keep_alive`
+#[cfg_attr(not(feature = "tokio"), allow(dead_code))]
+enum ParseState {
+    Head,
+    Length(ResponseHead, usize),
+    Chunked(ResponseHead, ChunkedDecoder, Vec<u8>),
+    Close(ResponseHead),
+    Done { keep_alive: bool },
+}
+
+impl ResponseParser {
+    /// A parser for the response to a `method` request
+    pub(crate) fn new(method: Method) -> Self {
+        ResponseParser {
+            method,
+            buf: Vec::new(),
+            received: false,
+            state: ParseState::Head,
+        }
+    }
+
+    pub(crate) fn push(&mut self, input: &[u8]) {
+        self.received |= !input.is_empty();
+        self.buf.extend_from_slice(input);
+    }
+
+    /// Whether any bytes of the response have arrived yet
+    #[cfg(feature = "tokio")]
+    pub(crate) fn received_any(&self) -> bool {
+        self.received
+    }
+
+    /// Whether the connection can be reused now that the response is complete
+    #[cfg(feature = "tokio")]
+    pub(crate) fn keep_alive(&self) -> bool {
+        matches!(self.state, ParseState::Done { keep_alive: true })
+    }
+
+    /// Parses as much as possible, returning the response once it is complete
+    pub(crate) fn advance(&mut self) -> Result<Option<Response>, SendError> {
+        loop {
+            let state = std::mem::replace(&mut self.state, ParseState::Done { keep_alive: false });
+            match state {
+                ParseState::Head => {
+                    let Some((head, head_len)) = parse_head(&self.buf)? else {
+                        self.state = ParseState::Head;
+                        return Ok(None);
+                    };
+                    self.buf.drain(..head_len);
+                    self.state = match framing(&self.method, &head)? {
+                        _ if head.is_interim() => ParseState::Head,
+                        ResponseFraming::Empty => {
+                            return Ok(Some(self.complete(head, Vec::new(), true)))
+                        }
+                        ResponseFraming::Length(len) => {
+                            let len = usize::try_from(len).map_err(|_| {
+                                SendError::InvalidResponse("content-length is too large")
+                            })?;
+                            ParseState::Length(head, len)
+                        }
+                        ResponseFraming::Chunked => {
+                            ParseState::Chunked(head, ChunkedDecoder::default(), Vec::new())
+                        }
+                        ResponseFraming::Close => ParseState::Close(head),
+                    };
+                }
+                ParseState::Length(head, len) if self.buf.len() >= len => {
+                    let body = self.buf.drain(..len).collect();
+                    return Ok(Some(self.complete(head, body, true)));
+                }
+                ParseState::Chunked(head, mut decoder, mut body) => {
+                    let used = decoder.decode(&self.buf, &mut body)?;
+                    self.buf.drain(..used);
+                    if decoder.is_done() {
+                        return Ok(Some(self.complete(head, body, true)));
+                    }
+                    self.state = ParseState::Chunked(head, decoder, body);
+                    return Ok(None);
+                }
+                state => {
+                    self.state = state;
+                    return Ok(None);
+                }
+            }
+        }
+    }
+
+    /// Finishes the response once the server has closed the connection
+    pub(crate) fn finish(&mut self) -> Result<Response, SendError> {
+        match std::mem::replace(&mut self.state, ParseState::Done { keep_alive: false }) {
+            ParseState::Close(head) => {
+                let body = std::mem::take(&mut self.buf);
+                Ok(self.complete(head, body, false))
+            }
+            ParseState::Head => Err(SendError::InvalidResponse(
+                "connection closed before the response head",
+            )),
+            _ => Err(SendError::InvalidResponse("connection closed mid-body")),
+        }
+    }
+
+    fn complete(&mut self, head: ResponseHead, body: Vec<u8>, delimited: bool) -> Response {
+        self.state = ParseState::Done {
+            keep_alive: delimited && head.keep_alive && self.buf.is_empty(),
+        };
+        head.into_response(body)
+    }
+}
+
+/// The status line and headers of a response
+#[derive(Debug)]
+pub(crate) struct ResponseHead {
+    pub(crate) status: u16,
+    pub(crate) reason: String,
+    pub(crate) headers: HeaderMap,
+    /// Whether the server is willing to keep the connection open
+    pub(crate) keep_alive: bool,
+}
+
+impl ResponseHead {
+    /// Whether this is a `1xx` response that the real one follows
+    pub(crate) fn is_interim(&self) -> bool {
+        (100..200).contains(&self.status) && self.status != 101
+    }
+
+    pub(crate) fn into_response(self, body: Vec<u8>) -> Response {
+        Response {
+            status: self.status,
+            reason: self.reason,
+            headers: self.headers,
+            body,
+        }
+    }
+}
+
+/// Parses a complete response head from the start of `buf`, returning it and its length in
+/// bytes, or `None` if more input is needed
+pub(crate) fn parse_head(buf: &[u8]) -> Result<Option<(ResponseHead, usize)>, SendError> {
+    let Some(end) = buf.windows(4).position(|w| w == b"\r\n\r\n") else {
+        if buf.len() > MAX_HEAD_LEN {
+            return Err(SendError::InvalidResponse("response head is too large"));
+        }
+        return Ok(None);
+    };
+    let head = std::str::from_utf8(&buf[..end])
+        .map_err(|_| SendError::InvalidResponse("response head isn't valid UTF-8"))?;
+    let mut lines = head.split("\r\n");
+
+    let status_line = lines.next().unwrap_or_default();
+    let invalid_status = || SendError::InvalidResponse("malformed status line");
+    let rest = status_line
+        .strip_prefix("HTTP/1.")
+        .filter(|rest| rest.starts_with(['0', '1']))
+        .ok_or_else(invalid_status)?;
+    let http10 = rest.starts_with('0');
+    let rest = rest[1..].strip_prefix(' ').ok_or_else(invalid_status)?;
+    let (code, reason) = rest.split_once(' ').unwrap_or((rest, ""));
+    if code.len() != 3 || !code.bytes().all(|b| b.is_ascii_digit()) {
+        return Err(invalid_status());
+    }
+    let status = code.parse().map_err(|_| invalid_status())?;
+
+    let headers = parse_header_lines(lines).map_err(SendError::InvalidResponse)?;
+
+    // HTTP/1.1 connections persist unless closed; HTTP/1.0 ones only if asked to
+    let connection = |option: &str| {
+        headers
+            .get_all("connection")
+            .iter()
+            .flat_map(|value| value.as_str().split(','))
+            .any(|token| token.trim().eq_ignore_ascii_case(option))
+    };
+    let keep_alive = match http10 {
+        true => connection("keep-alive"),
+        false => !connection("close"),
+    };
+
+    let head = ResponseHead {
+        status,
+        reason: reason.to_string(),
+        headers,
+        keep_alive,
+    };
+    Ok(Some((head, end + 4)))
+}
+
+fn parse_header_lines<'a>(lines: impl Iterator<Item = &'a str>) -> Result<HeaderMap, &'static str> {
+    let mut headers = HeaderMap::new();
+    for line in lines {
+        if line.starts_with([' ', '\t']) {
+            return Err("folded header lines aren't supported");
+        }
+        let (name, value) = line.split_once(':').ok_or("malformed header line")?;
+        headers.append(
+            HeaderName::try_from(name).map_err(|_| "malformed header name")?,
+            HeaderValue::try_from(value.trim_matches([' ', '\t']))
+                .map_err(|_| "malformed header value")?,
+        );
+    }
+    Ok(headers)
+}
+
+/// How the response body is delimited
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum ResponseFraming {
+    Empty,
+    Length(u64),
+    Chunked,
+    /// The body runs until the server closes the connection
+    Close,
+}
+
+/// Works out how the body of a response to `method` is delimited (RFC 9112 §6.3)
+pub(crate) fn framing(method: &Method, head: &ResponseHead) -> Result<ResponseFraming, SendError> {
+    let status = head.status;
+    if *method == Method::HEAD
+        || (100..200).contains(&status)
+        || status == 204
+        || status == 304
+        || (*method == Method::CONNECT && (200..300).contains(&status))
+    {
+        return Ok(ResponseFraming::Empty);
+    }
+
+    let codings = head.headers.get_all("transfer-encoding");
+    if !codings.is_empty() {
+        let last = codings
+            .iter()
+            .flat_map(|value| value.as_str().split(','))
+            .map(str::trim)
+            .next_back()
+            .unwrap_or_default();
+        return Ok(match last.eq_ignore_ascii_case("chunked") {
+            true => ResponseFraming::Chunked,
+            false => ResponseFraming::Close,
+        });
+    }
+
+    match head.headers.typed_get::<ContentLength>() {
+        Ok(Some(ContentLength(len))) => Ok(ResponseFraming::Length(len)),
+        Ok(None) => Ok(ResponseFraming::Close),
+        Err(_) => Err(SendError::InvalidResponse("malformed content-length")),
+    }
+}
+
+/// Decodes a `Transfer-Encoding: chunked` body fed to it in pieces
+#[derive(Debug, Default)]
+pub(crate) struct ChunkedDecoder {
+    state: ChunkState,
+}
+
+#[derive(Debug, Default, Clone, Copy)]
+enum ChunkState {
+    #[default]
+    Size,
+    Data(u64),
+    DataEnd,
+    Trailers,
+    Done,
+}
+
+impl ChunkedDecoder {
+    /// Appends the data decoded from `input` to `body`, returning how many bytes of `input` were
+    /// used; the rest must be passed again along with more input
+    pub(crate) fn decode(&mut self, input: &[u8], body: &mut Vec<u8>) -> Result<usize, SendError> {
+        let invalid = || SendError::InvalidResponse("malformed chunked body");
+        let mut pos = 0;
+        loop {
+            let rest = &input[pos..];
+            match self.state {
+                ChunkState::Size => {
+                    let Some((line, used)) = take_line(rest) else {
+                        return Ok(pos);
+                    };
+                    let size = line.split(|&b| b == b';').next().unwrap_or_default();
+                    let size = std::str::from_utf8(size).map_err(|_| invalid())?.trim();
+                    if size.is_empty() || !size.bytes().all(|b| b.is_ascii_hexdigit()) {
+                        return Err(invalid());
+                    }
+                    let size = u64::from_str_radix(size, 16).map_err(|_| invalid())?;
+                    self.state = match size {
+                        0 => ChunkState::Trailers,
+                        size => ChunkState::Data(size),
+                    };
+                    pos += used;
+                }
+                ChunkState::Data(remaining) => {
+                    let n = remaining.min(rest.len() as u64) as usize;
+                    if n == 0 {
+                        return Ok(pos);
+                    }
+                    body.extend_from_slice(&rest[..n]);
+                    self.state = match remaining - n as u64 {
+                        0 => ChunkState::DataEnd,
+                        remaining => ChunkState::Data(remaining),
+                    };
+                    pos += n;
+                }
+                ChunkState::DataEnd => {
+                    if rest.len() < 2 {
+                        return Ok(pos);
+                    }
+                    if &rest[..2] != b"\r\n" {
+                        return Err(invalid());
+                    }
+                    self.state = ChunkState::Size;
+                    pos += 2;
+                }
+                ChunkState::Trailers => {
+                    let Some((line, used)) = take_line(rest) else {
+                        return Ok(pos);
+                    };
+                    if line.is_empty() {
+                        self.state = ChunkState::Done;
+                    }
+                    pos += used;
+                }
+                ChunkState::Done => return Ok(pos),
+            }
+        }
+    }
+
+    pub(crate) fn is_done(&self) -> bool {
+        matches!(self.state, ChunkState::Done)
+    }
+}
+
+/// Splits off a CRLF-terminated line, returning it without the CRLF and the bytes it used
+fn take_line(input: &[u8]) -> Option<(&[u8], usize)> {
+    let end = input.windows(2).position(|w| w == b"\r\n")?;
+    Some((&input[..end], end + 2))
+}
+
+// Wire format
+/// Bytes that aren't a valid HTTP/1.1 request
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InvalidRequest(&'static str);
+
+impl fmt::Display for InvalidRequest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid HTTP/1.1 request: {}", self.0)
+    }
+}
+
+impl std::error::Error for InvalidRequest {}
+
+/// Writes `request` with an absolute-form target, adding `Host` and `Content-Length` unless set
+pub(crate) fn serialize_request(request: &Request) -> io::Result<Vec<u8>> {
+    let body = match &request.body {
+        None => None,
+        Some(body) => match (body.as_bytes(), body.path()) {
+            (Some(bytes), _) => Some(bytes.to_vec()),
+            (None, Some(path)) => Some(std::fs::read(path)?),
+            (None, None) => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "stream bodies can't be serialized without consuming them",
+                ))
+            }
+        },
+    };
+    let framing = match &body {
+        Some(body) if !request.headers.contains_key("content-length") => {
+            RequestFraming::Length(body.len() as u64)
+        }
+        _ => RequestFraming::Unframed,
+    };
+    let url = &request.url;
+    let target = format!(
+        "{}://{}{}",
+        url.scheme(),
+        host_header(url),
+        origin_form(url)
+    );
+    let mut bytes = request_head(
+        &target,
+        url,
+        &request.method,
+        &request.headers,
+        framing,
+        false,
+    );
+    bytes.extend_from_slice(&body.unwrap_or_default());
+    Ok(bytes)
+}
+
+/// Parses exactly one request, dropping `Host` and `Content-Length` if they hold the values
+/// `serialize_request` would derive, and checking the body with `method_and_body()`
+pub(crate) fn parse_request(bytes: &[u8]) -> Result<Request, InvalidRequest> {
+    let end = bytes
+        .windows(4)
+        .position(|w| w == b"\r\n\r\n")
+        .ok_or(InvalidRequest("missing blank line after the headers"))?;
+    let head =
+        std::str::from_utf8(&bytes[..end]).map_err(|_| InvalidRequest("head isn't valid UTF-8"))?;
+    let mut lines = head.split("\r\n");
+
+    let mut request_line = lines.next().unwrap_or_default().split(' ');
+    let (Some(method), Some(target), Some("HTTP/1.1"), None) = (
+        request_line.next(),
+        request_line.next(),
+        request_line.next(),
+        request_line.next(),
+    ) else {
+        return Err(InvalidRequest("malformed request line"));
+    };
+    let method = Method::from_token(method).map_err(|_| InvalidRequest("malformed method"))?;
+    let mut headers = parse_header_lines(lines).map_err(InvalidRequest)?;
+
+    // An origin-form target only has the path, so the rest comes from `Host`
+    let url = match target.strip_prefix('/') {
+        Some(_) => match headers.get_all("host") {
+            [host] => Url::parse(&format!("http://{host}{target}")),
+            _ => return Err(InvalidRequest("origin-form target needs exactly one host")),
+        },
+        None => Url::parse(target),
+    }
+    .map_err(|_| InvalidRequest("malformed request target"))?;
+    let derived_host = host_header(&url);
+    if headers
+        .get_all("host")
+        .iter()
+        .map(HeaderValue::as_str)
+        .eq([derived_host.as_str()])
+    {
+        headers.remove("host");
+    }
+
+    let rest = &bytes[end + 4..];
+    let body = if headers.contains_key("transfer-encoding") {
+        if headers.contains_key("content-length") {
+            return Err(InvalidRequest("both content-length and transfer-encoding"));
+        }
+        let chunked_only = headers
+            .get_all("transfer-encoding")
+            .iter()
+            .map(HeaderValue::as_str)
+            .eq(["chunked"]);
+        if !chunked_only {
+            return Err(InvalidRequest("unsupported transfer-encoding"));
+        }
+        let mut decoder = ChunkedDecoder::default();
+        let mut body = Vec::new();
+        let used = decoder
+            .decode(rest, &mut body)
+            .map_err(|_| InvalidRequest("malformed chunked body"))?;
+        if !decoder.is_done() || used != rest.len() {
+            return Err(InvalidRequest(
+                "chunked body doesn't end where the input does",
+            ));
+        }
+        // The body is decoded now, so it's framed by `Content-Length` from here on
+        headers.remove("transfer-encoding");
+        Some(body)
+    } else {
+        match headers.typed_get::<ContentLength>() {
+            Ok(Some(ContentLength(len))) if len == rest.len() as u64 => Some(rest.to_vec()),
+            Ok(Some(_)) => return Err(InvalidRequest("body length doesn't match content-length")),
+            Ok(None) if rest.is_empty() => None,
+            Ok(None) => return Err(InvalidRequest("body without content-length")),
+            Err(_) => return Err(InvalidRequest("malformed content-length")),
+        }
+    };
+    // `Content-Length: 0` is how many clients send a GET or an empty POST, so it means no body
+    // unless the method requires one
+    let body = body.filter(|body| !body.is_empty() || method.body_rule() == BodyRule::Required);
+    let derived_len = body.as_ref().map_or(0, Vec::len).to_string();
+    if headers
+        .get_all("content-length")
+        .iter()
+        .map(HeaderValue::as_str)
+        .eq([derived_len.as_str()])
+    {
+        headers.remove("content-length");
+    }
+
+    // Hold parsed requests to the same rules as built ones, e.g. no GET with a body
+    let mut builder = RequestBuilder::new()
+        .url(url)
+        .map_err(|_| InvalidRequest("malformed request target"))?;
+    *builder.headers_mut() = headers;
+    let builder = builder
+        .method_and_body(method, body.map(Payload::bytes))
+        .map_err(|err| match err {
+            BodyRuleError::NotAllowed(_) => InvalidRequest("method can't have a body"),
+            BodyRuleError::Required(_) => InvalidRequest("method must have a body"),
+            BodyRuleError::MissingContentType => InvalidRequest("body without content-type"),
+        })?;
+    Ok(builder.build())
+}
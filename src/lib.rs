@@ -0,0 +1,36 @@
+//! A basic example of using a TypeState Builder pattern in Rust
+//!
+//! `RequestBuilder` tracks the URL, method and body of a request in its type
+//! parameters, so `build()` is only available once the request is valid.
+
+pub mod body;
+pub mod builder;
+pub mod client;
+pub mod curl;
+#[cfg(feature = "json")]
+pub mod har;
+pub mod header;
+pub mod http1;
+pub mod method;
+pub mod mime;
+pub mod multipart;
+#[cfg(feature = "tokio")]
+pub mod pool;
+pub mod reader;
+pub mod request;
+pub mod response;
+pub mod state;
+pub mod template;
+pub mod transport;
+pub mod url;
+
+pub use body::Payload;
+pub use builder::RequestBuilder;
+pub use client::Client;
+pub use header::HeaderMap;
+pub use method::{HttpMethod, Method};
+pub use mime::Mime;
+pub use reader::ResponseReader;
+pub use request::{Parts, Request};
+pub use response::Response;
+pub use url::Url;
@@ -0,0 +1,394 @@
+use std::fmt;
+use std::marker::PhantomData;
+
+use crate::header::is_tchar;
+use crate::mime::is_token;
+
+/// HTTP method of a built `Request`
+#[derive(Default, Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Method {
+    #[default]
+    GET,
+    POST,
+    PUT,
+    PATCH,
+    DELETE,
+    HEAD,
+    OPTIONS,
+    TRACE,
+    CONNECT,
+    /// Any other method token, e.g. `PROPFIND` or `PURGE`
+    Extension(ExtensionMethod),
+}
+
+/// A method token that isn't one of the standard ones, only made by `Method::from_token`
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ExtensionMethod(String);
+
+impl ExtensionMethod {
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+/// A method that isn't a valid token, e.g. one containing a space or CR/LF
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InvalidMethod(pub(crate) String);
+
+impl fmt::Display for InvalidMethod {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid method {:?}", self.0)
+    }
+}
+
+impl std::error::Error for InvalidMethod {}
+
+impl Method {
+    /// Returns the standard variant for `token`, or `Method::Extension` for any other valid token
+    ///
+    /// Tokens are case-sensitive, so `get` is an extension method rather than GET.
+    ///
+    /// ```
+    /// use typestate_test::Method;
+    ///
+    /// assert_eq!(Method::from_token("PUT")?, Method::PUT);
+    /// assert_eq!(Method::from_token("PROPFIND")?.as_str(), "PROPFIND");
+    /// assert!(Method::from_token("GET / HTTP/1.1\r\nX-Evil: 1\r\n\r\nPOST").is_err());
+    /// # Ok::<(), typestate_test::method::InvalidMethod>(())
+    /// ```
+    pub fn from_token(token: &str) -> Result<Self, InvalidMethod> {
+        if !is_token(token) {
+            return Err(InvalidMethod(token.to_string()));
+        }
+        Ok(match token {
+            "GET" => Method::GET,
+            "POST" => Method::POST,
+            "PUT" => Method::PUT,
+            "PATCH" => Method::PATCH,
+            "DELETE" => Method::DELETE,
+            "HEAD" => Method::HEAD,
+            "OPTIONS" => Method::OPTIONS,
+            "TRACE" => Method::TRACE,
+            "CONNECT" => Method::CONNECT,
+            other => Method::Extension(ExtensionMethod(other.to_string())),
+        })
+    }
+
+    /// The rule the method's typestate enforces at compile time; extension methods may or may
+    /// not have a body
+    pub fn body_rule(&self) -> BodyRule {
+        fn rule<M: HttpMethod>() -> BodyRule {
+            M::BodyPolicy::RULE
+        }
+        match self {
+            Method::GET => rule::<Get>(),
+            Method::POST => rule::<Post>(),
+            Method::PUT => rule::<Put>(),
+            Method::PATCH => rule::<Patch>(),
+            Method::DELETE => rule::<Delete>(),
+            Method::HEAD => rule::<Head>(),
+            Method::OPTIONS => rule::<Options>(),
+            Method::TRACE => rule::<Trace>(),
+            Method::CONNECT => rule::<Connect>(),
+            Method::Extension(_) => BodyRule::Optional,
+        }
+    }
+
+    /// Whether sending the request twice has the same effect as sending it once (RFC 9110 §9.2.2);
+    /// extension methods are assumed not to be
+    pub fn is_idempotent(&self) -> bool {
+        matches!(
+            self,
+            Method::GET
+                | Method::PUT
+                | Method::DELETE
+                | Method::HEAD
+                | Method::OPTIONS
+                | Method::TRACE
+        )
+    }
+
+    /// The method token sent on the request line
+    pub fn as_str(&self) -> &str {
+        match self {
+            Method::GET => "GET",
+            Method::POST => "POST",
+            Method::PUT => "PUT",
+            Method::PATCH => "PATCH",
+            Method::DELETE => "DELETE",
+            Method::HEAD => "HEAD",
+            Method::OPTIONS => "OPTIONS",
+            Method::TRACE => "TRACE",
+            Method::CONNECT => "CONNECT",
+            Method::Extension(token) => token.as_str(),
+        }
+    }
+}
+
+/// The method token, e.g. `"GET"`
+#[cfg(feature = "serde")]
+impl serde::Serialize for Method {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Method {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let token = <String as serde::Deserialize>::deserialize(deserializer)?;
+        Method::from_token(&token).map_err(serde::de::Error::custom)
+    }
+}
+
+/// A method usable as the "method set" state of a `RequestBuilder`
+///
+/// Custom verbs implement this to get the same typestate checks as the built-in ones:
+///
+/// ```
+/// use typestate_test::method::{HttpMethod, OptionalBody, ReturnsBody};
+/// use typestate_test::RequestBuilder;
+///
+/// struct Propfind;
+///
+/// impl HttpMethod for Propfind {
+///     const TOKEN: &'static str = "PROPFIND";
+///     type BodyPolicy = OptionalBody;
+/// }
+///
+/// // Lets `ResponseReader` read the multi-status body
+/// impl ReturnsBody for Propfind {}
+///
+/// let req = RequestBuilder::new()
+///     .url("https://example.com/files")?
+///     .method(Propfind)
+///     .header("Depth", "1")?
+///     .build();
+///
+/// assert_eq!(req.method().as_str(), "PROPFIND");
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+///
+/// A token that names a standard method must keep that method's body policy, so a "GET" can't
+/// be given a body this way:
+///
+/// ```compile_fail,E0080
+/// use typestate_test::method::{HttpMethod, OptionalBody};
+/// use typestate_test::RequestBuilder;
+///
+/// struct FakeGet;
+///
+/// impl HttpMethod for FakeGet {
+///     const TOKEN: &'static str = "GET";
+///     type BodyPolicy = OptionalBody;
+/// }
+///
+/// let req = RequestBuilder::new()
+///     .url("https://example.com")?
+///     .method(FakeGet)
+///     .text("x")
+///     .build();
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+///
+/// And the token can't smuggle extra lines into the request:
+///
+/// ```compile_fail,E0080
+/// use typestate_test::method::{HttpMethod, NoBodyAllowed};
+/// use typestate_test::RequestBuilder;
+///
+/// struct Smuggle;
+///
+/// impl HttpMethod for Smuggle {
+///     const TOKEN: &'static str = "GET / HTTP/1.1\r\nX-Evil: 1\r\n\r\nPOST";
+///     type BodyPolicy = NoBodyAllowed;
+/// }
+///
+/// let req = RequestBuilder::new()
+///     .url("https://example.com")?
+///     .method(Smuggle)
+///     .build();
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+pub trait HttpMethod {
+    /// The method token sent on the request line
+    ///
+    /// `build()` fails to compile if this isn't a valid token, or if it names a standard method
+    /// but `BodyPolicy` differs from that method's.
+    const TOKEN: &'static str;
+    /// Whether requests with this method can, must or must not have a body
+    type BodyPolicy: BodyPolicy;
+}
+
+/// Checked when `build()` is monomorphized, so a bad `HttpMethod` impl is a compile error
+pub(crate) struct CheckedToken<M>(PhantomData<M>);
+
+impl<M: HttpMethod> CheckedToken<M> {
+    pub(crate) const OK: () = {
+        assert!(
+            is_const_token(M::TOKEN),
+            "HttpMethod::TOKEN must be a non-empty token without spaces or control characters"
+        );
+        assert!(
+            matches_standard_policy(M::TOKEN, M::BodyPolicy::RULE),
+            "HttpMethod::TOKEN names a standard method with a different BodyPolicy"
+        );
+    };
+}
+
+/// The runtime `Method` for `M`, failing to compile if `M::TOKEN` is invalid
+pub(crate) fn method_of<M: HttpMethod>() -> Method {
+    let () = CheckedToken::<M>::OK;
+    Method::from_token(M::TOKEN).expect("checked at compile time")
+}
+
+const fn is_const_token(token: &str) -> bool {
+    let bytes = token.as_bytes();
+    let mut i = 0;
+    while i < bytes.len() {
+        if !is_tchar(bytes[i]) {
+            return false;
+        }
+        i += 1;
+    }
+    !bytes.is_empty()
+}
+
+/// Method tokens are case-sensitive (RFC 9110 §9.1), so `get` isn't GET, the same as in
+/// `Method::from_token`
+const fn matches_standard_policy(token: &str, rule: BodyRule) -> bool {
+    const STANDARD: [(&str, BodyRule); 9] = [
+        (Get::TOKEN, <Get as HttpMethod>::BodyPolicy::RULE),
+        (Head::TOKEN, <Head as HttpMethod>::BodyPolicy::RULE),
+        (Trace::TOKEN, <Trace as HttpMethod>::BodyPolicy::RULE),
+        (Connect::TOKEN, <Connect as HttpMethod>::BodyPolicy::RULE),
+        (Post::TOKEN, <Post as HttpMethod>::BodyPolicy::RULE),
+        (Delete::TOKEN, <Delete as HttpMethod>::BodyPolicy::RULE),
+        (Options::TOKEN, <Options as HttpMethod>::BodyPolicy::RULE),
+        (Put::TOKEN, <Put as HttpMethod>::BodyPolicy::RULE),
+        (Patch::TOKEN, <Patch as HttpMethod>::BodyPolicy::RULE),
+    ];
+    let mut i = 0;
+    while i < STANDARD.len() {
+        let (standard, standard_rule) = STANDARD[i];
+        if const_eq(token, standard) {
+            return rule as u8 == standard_rule as u8;
+        }
+        i += 1;
+    }
+    true
+}
+
+const fn const_eq(a: &str, b: &str) -> bool {
+    let (a, b) = (a.as_bytes(), b.as_bytes());
+    if a.len() != b.len() {
+        return false;
+    }
+    let mut i = 0;
+    while i < a.len() {
+        if a[i] != b[i] {
+            return false;
+        }
+        i += 1;
+    }
+    true
+}
+
+/// Methods whose successful responses carry a body that `ResponseReader` lets you read
+///
+/// HEAD responses only describe the body a GET would have returned, and a successful CONNECT
+/// turns the connection into a tunnel, so neither implements this.
+pub trait ReturnsBody: HttpMethod {}
+
+// Body Policies
+/// The method never has a body, e.g. GET
+pub struct NoBodyAllowed;
+/// The method may or may not have a body, e.g. POST
+pub struct OptionalBody;
+/// The method must have a body, e.g. PUT
+pub struct RequiredBody;
+
+/// Implemented by `NoBodyAllowed`, `OptionalBody` and `RequiredBody`
+pub trait BodyPolicy: sealed::Sealed {
+    /// The same policy, for checking at runtime
+    const RULE: BodyRule;
+}
+impl BodyPolicy for NoBodyAllowed {
+    const RULE: BodyRule = BodyRule::NotAllowed;
+}
+impl BodyPolicy for OptionalBody {
+    const RULE: BodyRule = BodyRule::Optional;
+}
+impl BodyPolicy for RequiredBody {
+    const RULE: BodyRule = BodyRule::Required;
+}
+
+/// A `BodyPolicy` checked at runtime, for methods only known once a request has been parsed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BodyRule {
+    NotAllowed,
+    Optional,
+    Required,
+}
+
+/// A body broke its method's `BodyRule`, or was set without a content type
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BodyRuleError {
+    NotAllowed(Method),
+    Required(Method),
+    MissingContentType,
+}
+
+impl fmt::Display for BodyRuleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BodyRuleError::NotAllowed(method) => {
+                write!(f, "{} requests can't have a body", method.as_str())
+            }
+            BodyRuleError::Required(method) => {
+                write!(f, "{} requests must have a body", method.as_str())
+            }
+            BodyRuleError::MissingContentType => f.write_str("a body needs a content type"),
+        }
+    }
+}
+
+impl std::error::Error for BodyRuleError {}
+
+mod sealed {
+    pub trait Sealed {}
+    impl Sealed for super::NoBodyAllowed {}
+    impl Sealed for super::OptionalBody {}
+    impl Sealed for super::RequiredBody {}
+}
+
+macro_rules! http_method {
+    ($name:ident, $token:literal, $policy:ty) => {
+        #[doc = concat!("The `", $token, "` method state")]
+        #[derive(Default, Debug, Clone, Copy)]
+        pub struct $name;
+
+        impl HttpMethod for $name {
+            const TOKEN: &'static str = $token;
+            type BodyPolicy = $policy;
+        }
+    };
+}
+
+http_method!(Get, "GET", NoBodyAllowed);
+http_method!(Head, "HEAD", NoBodyAllowed);
+http_method!(Trace, "TRACE", NoBodyAllowed);
+http_method!(Connect, "CONNECT", NoBodyAllowed);
+http_method!(Post, "POST", OptionalBody);
+http_method!(Delete, "DELETE", OptionalBody);
+http_method!(Options, "OPTIONS", OptionalBody);
+http_method!(Put, "PUT", RequiredBody);
+http_method!(Patch, "PATCH", RequiredBody);
+
+impl ReturnsBody for Get {}
+impl ReturnsBody for Trace {}
+impl ReturnsBody for Post {}
+impl ReturnsBody for Delete {}
+impl ReturnsBody for Options {}
+impl ReturnsBody for Put {}
+impl ReturnsBody for Patch {}
@@ -0,0 +1,183 @@
+use std::borrow::Cow;
+use std::fmt;
+use std::str::FromStr;
+
+use crate::header::{is_tchar, is_value_byte};
+
+/// A media type such as `application/json` or `text/html; charset=utf-8`
+///
+/// The type, subtype and parameter names are lowercased when parsed.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Mime {
+    type_: Cow<'static, str>,
+    subtype: Cow<'static, str>,
+    params: Vec<(String, String)>,
+}
+
+/// The string isn't a valid `type/subtype; name=value` media type
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InvalidMime(String);
+
+impl fmt::Display for InvalidMime {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid media type {:?}", self.0)
+    }
+}
+
+impl std::error::Error for InvalidMime {}
+
+impl Mime {
+    pub const TEXT_PLAIN: Mime = Mime::from_static("text", "plain");
+    pub const TEXT_HTML: Mime = Mime::from_static("text", "html");
+    pub const APPLICATION_JSON: Mime = Mime::from_static("application", "json");
+    pub const APPLICATION_OCTET_STREAM: Mime = Mime::from_static("application", "octet-stream");
+    pub const APPLICATION_WWW_FORM_URLENCODED: Mime =
+        Mime::from_static("application", "x-www-form-urlencoded");
+    pub const MULTIPART_FORM_DATA: Mime = Mime::from_static("multipart", "form-data");
+    pub const STAR_STAR: Mime = Mime::from_static("*", "*");
+
+    const fn from_static(type_: &'static str, subtype: &'static str) -> Self {
+        Mime {
+            type_: Cow::Borrowed(type_),
+            subtype: Cow::Borrowed(subtype),
+            params: Vec::new(),
+        }
+    }
+
+    /// Parses a media type, e.g. `text/html; charset="utf-8"`
+    pub fn parse(s: &str) -> Result<Self, InvalidMime> {
+        let invalid = || InvalidMime(s.to_string());
+        if !s.bytes().all(is_value_byte) {
+            return Err(invalid());
+        }
+        let mut parts = split_quoted(s, ';').into_iter();
+        let essence = parts.next().ok_or_else(invalid)?.trim();
+        let (type_, subtype) = essence.split_once('/').ok_or_else(invalid)?;
+        if !is_token(type_) || !is_token(subtype) {
+            return Err(invalid());
+        }
+
+        let mut params = Vec::new();
+        for param in parts {
+            let (name, value) = param.trim().split_once('=').ok_or_else(invalid)?;
+            let value = match value.strip_prefix('"') {
+                Some(quoted) => unquote(quoted.strip_suffix('"').ok_or_else(invalid)?),
+                None if is_token(value) => value.to_string(),
+                None => return Err(invalid()),
+            };
+            if !is_token(name) {
+                return Err(invalid());
+            }
+            params.push((name.to_ascii_lowercase(), value));
+        }
+
+        Ok(Mime {
+            type_: Cow::Owned(type_.to_ascii_lowercase()),
+            subtype: Cow::Owned(subtype.to_ascii_lowercase()),
+            params,
+        })
+    }
+
+    /// The top-level type, e.g. `text` in `text/html`
+    pub fn type_(&self) -> &str {
+        &self.type_
+    }
+
+    /// The subtype, e.g. `html` in `text/html`
+    pub fn subtype(&self) -> &str {
+        &self.subtype
+    }
+
+    /// `type/subtype` without any parameters
+    pub fn essence(&self) -> String {
+        format!("{}/{}", self.type_, self.subtype)
+    }
+
+    /// The value of parameter `name`, e.g. `charset`
+    pub fn param(&self, name: &str) -> Option<&str> {
+        self.params
+            .iter()
+            .find(|(n, _)| n.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    pub fn params(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.params.iter().map(|(n, v)| (n.as_str(), v.as_str()))
+    }
+
+    /// Returns this media type with parameter `name` set to `value`, or an error if `name`
+    /// isn't a token or `value` contains a control character
+    pub fn with_param(mut self, name: &str, value: impl Into<String>) -> Result<Self, InvalidMime> {
+        let value = value.into();
+        if !is_token(name) || !value.bytes().all(is_value_byte) {
+            return Err(InvalidMime(format!("{self}; {name}={value}")));
+        }
+        let name = name.to_ascii_lowercase();
+        match self.params.iter_mut().find(|(n, _)| *n == name) {
+            Some((_, v)) => *v = value,
+            None => self.params.push((name, value)),
+        }
+        Ok(self)
+    }
+}
+
+impl fmt::Display for Mime {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", self.type_, self.subtype)?;
+        for (name, value) in &self.params {
+            if is_token(value) {
+                write!(f, "; {name}={value}")?;
+            } else {
+                let escaped = value.replace('\\', "\\\\").replace('"', "\\\"");
+                write!(f, "; {name}=\"{escaped}\"")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Mime {
+    type Err = InvalidMime;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Mime::parse(s)
+    }
+}
+
+pub(crate) fn is_token(s: &str) -> bool {
+    !s.is_empty() && s.bytes().all(is_tchar)
+}
+
+/// Splits `s` on `sep`, ignoring separators inside quoted strings
+pub(crate) fn split_quoted(s: &str, sep: char) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut start = 0;
+    let mut in_quotes = false;
+    let mut escaped = false;
+    for (i, c) in s.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' if in_quotes => escaped = true,
+            '"' => in_quotes = !in_quotes,
+            c if c == sep && !in_quotes => {
+                parts.push(&s[start..i]);
+                start = i + c.len_utf8();
+            }
+            _ => {}
+        }
+    }
+    parts.push(&s[start..]);
+    parts
+}
+
+fn unquote(s: &str) -> String {
+    let mut unquoted = String::with_capacity(s.len());
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => unquoted.extend(chars.next()),
+            c => unquoted.push(c),
+        }
+    }
+    unquoted
+}
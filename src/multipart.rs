@@ -0,0 +1,181 @@
+use std::collections::hash_map::RandomState;
+use std::collections::VecDeque;
+use std::hash::{BuildHasher, Hasher};
+use std::io::{self, Read};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::body::Payload;
+use crate::mime::Mime;
+
+/// A `multipart/form-data` body made of text fields and file fields
+///
+/// ```
+/// use typestate_test::multipart::Multipart;
+/// use typestate_test::{Mime, RequestBuilder};
+///
+/// let form = Multipart::new()
+///     .text("title", "holiday")
+///     .file("photo", "beach.png", Mime::APPLICATION_OCTET_STREAM, vec![0x89, b'P', b'N', b'G']);
+///
+/// let req = RequestBuilder::new()
+///     .post()
+///     .url("https://example.com/upload")?
+///     .multipart(form)
+///     .build();
+///
+/// let content_type = req.content_type()?.unwrap();
+/// assert_eq!(content_type.essence(), "multipart/form-data");
+/// assert!(content_type.param("boundary").is_some());
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+#[derive(Debug)]
+pub struct Multipart {
+    boundary: String,
+    parts: Vec<Part>,
+}
+
+#[derive(Debug)]
+struct Part {
+    name: String,
+    filename: Option<String>,
+    content_type: Option<Mime>,
+    data: Payload,
+}
+
+impl Default for Multipart {
+    fn default() -> Self {
+        Multipart::new()
+    }
+}
+
+impl Multipart {
+    /// An empty form with a randomly generated boundary
+    pub fn new() -> Self {
+        Multipart {
+            boundary: generate_boundary(),
+            parts: Vec::new(),
+        }
+    }
+
+    /// Adds a text field
+    pub fn text(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.parts.push(Part {
+            name: name.into(),
+            filename: None,
+            content_type: None,
+            data: Payload::from(value.into()),
+        });
+        self
+    }
+
+    /// Adds a file field; `data` can be in memory, a path read lazily, or a stream
+    pub fn file(
+        mut self,
+        name: impl Into<String>,
+        filename: impl Into<String>,
+        content_type: Mime,
+        data: impl Into<Payload>,
+    ) -> Self {
+        self.parts.push(Part {
+            name: name.into(),
+            filename: Some(filename.into()),
+            content_type: Some(content_type),
+            data: data.into(),
+        });
+        self
+    }
+
+    /// The boundary separating the parts
+    pub fn boundary(&self) -> &str {
+        &self.boundary
+    }
+
+    /// `multipart/form-data; boundary=...`
+    pub fn content_type(&self) -> Mime {
+        Mime::MULTIPART_FORM_DATA
+            .with_param("boundary", self.boundary.as_str())
+            .expect("generated boundaries are valid parameter values")
+    }
+
+    /// Encodes the form, staying in memory unless a part is a file or stream
+    pub fn into_payload(self) -> Payload {
+        let mut segments = VecDeque::new();
+        for part in self.parts {
+            let mut head = format!(
+                "--{}\r\nContent-Disposition: form-data; name=\"{}\"",
+                self.boundary,
+                escape_quoted(&part.name)
+            );
+            if let Some(filename) = &part.filename {
+                head.push_str(&format!("; filename=\"{}\"", escape_quoted(filename)));
+            }
+            if let Some(content_type) = &part.content_type {
+                head.push_str(&format!("\r\nContent-Type: {content_type}"));
+            }
+            head.push_str("\r\n\r\n");
+            segments.push_back(Payload::from(head));
+            segments.push_back(part.data);
+            segments.push_back(Payload::from("\r\n"));
+        }
+        segments.push_back(Payload::from(format!("--{}--\r\n", self.boundary)));
+
+        if segments.iter().all(|segment| segment.as_bytes().is_some()) {
+            let bytes = segments
+                .iter()
+                .flat_map(|segment| segment.as_bytes().unwrap_or_default())
+                .copied()
+                .collect::<Vec<u8>>();
+            return Payload::bytes(bytes);
+        }
+        Payload::reader(SegmentReader {
+            segments,
+            current: None,
+        })
+    }
+}
+
+/// Reads each segment in turn, only opening a file once the reader reaches it
+struct SegmentReader {
+    segments: VecDeque<Payload>,
+    current: Option<Box<dyn Read + Send>>,
+}
+
+impl Read for SegmentReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        loop {
+            if let Some(current) = &mut self.current {
+                let n = current.read(buf)?;
+                if n > 0 || buf.is_empty() {
+                    return Ok(n);
+                }
+            }
+            match self.segments.pop_front() {
+                Some(segment) => self.current = Some(segment.into_reader()?),
+                None => return Ok(0),
+            }
+        }
+    }
+}
+
+/// Percent-encodes the characters that would break out of a quoted field name or filename
+fn escape_quoted(s: &str) -> String {
+    s.replace('"', "%22")
+        .replace('\r', "%0D")
+        .replace('\n', "%0A")
+}
+
+fn generate_boundary() -> String {
+    static COUNTER: AtomicU64 = AtomicU64::new(0);
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_nanos())
+        .unwrap_or_default();
+    let random = || {
+        let mut hasher = RandomState::new().build_hasher();
+        hasher.write_u128(nanos);
+        hasher.write_u64(COUNTER.fetch_add(1, Ordering::Relaxed));
+        hasher.finish()
+    };
+    format!("------------------------{:016x}{:016x}", random(), random())
+}
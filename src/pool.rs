@@ -0,0 +1,173 @@
+//! Async sending over tokio, keeping connections alive and reusing them per host and port
+
+use std::collections::HashMap;
+use std::io;
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+
+use crate::body::Payload;
+use crate::http1::{origin_form, request_framing, request_head, socket_port, unbracketed_host};
+use crate::http1::{ResponseParser, SendError};
+use crate::request::Request;
+use crate::response::Response;
+
+/// Connections are pooled by lowercased host and port
+type Key = (String, u16);
+
+/// Sends requests asynchronously, reusing idle keep-alive connections
+///
+/// Clones share the same idle connections.
+///
+/// ```no_run
+/// use typestate_test::pool::Pool;
+/// use typestate_test::RequestBuilder;
+///
+/// # async fn run() -> Result<(), Box<dyn std::error::Error>> {
+/// let pool = Pool::new();
+/// let req = RequestBuilder::new().get().url("http://localhost:8080/health")?.build();
+/// let res = pool.send(req).await?;
+/// assert!(res.is_success());
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct Pool {
+    idle: Arc<Mutex<HashMap<Key, Vec<TcpStream>>>>,
+    max_idle_per_host: usize,
+}
+
+impl Default for Pool {
+    fn default() -> Self {
+        Pool::new()
+    }
+}
+
+impl Pool {
+    /// An empty pool keeping up to 8 idle connections per host
+    pub fn new() -> Self {
+        Pool {
+            idle: Arc::default(),
+            max_idle_per_host: 8,
+        }
+    }
+
+    /// Sets how many idle connections are kept for each host and port
+    pub fn max_idle_per_host(mut self, max: usize) -> Self {
+        self.max_idle_per_host = max;
+        self
+    }
+
+    /// The number of idle connections currently kept open
+    pub fn idle_connections(&self) -> usize {
+        self.lock().values().map(Vec::len).sum()
+    }
+
+    /// Sends `request` on an idle connection if there is one, or a new one otherwise
+    ///
+    /// The request's timeout covers the whole exchange. File and stream bodies are read into
+    /// memory on a blocking thread first, so a request can be retried on a new connection if
+    /// the server had already closed the idle one. Once the whole request was written, only an
+    /// idempotent method is retried, since the server may have acted on it before closing.
+    pub async fn send(&self, request: Request) -> Result<Response, SendError> {
+        match request.timeout {
+            Some(timeout) => tokio::time::timeout(timeout, self.send_inner(request))
+                .await
+                .map_err(|_| io::Error::new(io::ErrorKind::TimedOut, "request timed out"))?,
+            None => self.send_inner(request).await,
+        }
+    }
+
+    async fn send_inner(&self, request: Request) -> Result<Response, SendError> {
+        let Request {
+            url,
+            method,
+            headers,
+            body,
+            ..
+        } = request;
+        let port = socket_port(&url)?;
+        let key = (url.host().to_ascii_lowercase(), port);
+
+        let body = match body {
+            Some(body) if body.as_bytes().is_none() => Some(Payload::bytes(
+                tokio::task::spawn_blocking(move || body.into_bytes())
+                    .await
+                    .map_err(io::Error::other)??,
+            )),
+            body => body,
+        };
+        let framing = request_framing(&method, &headers, body.as_ref())?;
+        let mut message = request_head(&origin_form(&url), &url, &method, &headers, framing, false);
+        message.extend_from_slice(
+            body.as_ref()
+                .and_then(Payload::as_bytes)
+                .unwrap_or_default(),
+        );
+
+        loop {
+            let (mut conn, reused) = match self.checkout(&key) {
+                Some(conn) => (conn, true),
+                None => {
+                    let conn = TcpStream::connect((unbracketed_host(&url), port)).await?;
+                    conn.set_nodelay(true)?;
+                    (conn, false)
+                }
+            };
+            let mut parser = ResponseParser::new(method.clone());
+            let (written, result) = match conn.write_all(&message).await {
+                Ok(()) => (true, read_response(&mut conn, &mut parser).await),
+                Err(err) => (false, Err(err.into())),
+            };
+            // The server closed the idle connection before we used it. If it couldn't have read
+            // the whole request, or the method is safe to repeat, try again on a new one
+            let retry = reused && !parser.received_any() && (!written || method.is_idempotent());
+            match result {
+                Ok(response) => {
+                    if parser.keep_alive() {
+                        self.checkin(key, conn);
+                    }
+                    return Ok(response);
+                }
+                Err(SendError::Io(_)) | Err(SendError::InvalidResponse(_)) if retry => {}
+                Err(err) => return Err(err),
+            }
+        }
+    }
+
+    fn checkout(&self, key: &Key) -> Option<TcpStream> {
+        self.lock().get_mut(key)?.pop()
+    }
+
+    fn checkin(&self, key: Key, conn: TcpStream) {
+        let mut idle = self.lock();
+        let conns = idle.entry(key).or_default();
+        if conns.len() < self.max_idle_per_host {
+            conns.push(conn);
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, HashMap<Key, Vec<TcpStream>>> {
+        // The map is never left half-updated, so a panic elsewhere doesn't poison it
+        self.idle
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+async fn read_response(
+    conn: &mut TcpStream,
+    parser: &mut ResponseParser,
+) -> Result<Response, SendError> {
+    let mut chunk = [0; 8 * 1024];
+    loop {
+        if let Some(response) = parser.advance()? {
+            return Ok(response);
+        }
+        match conn.read(&mut chunk).await? {
+            0 => return parser.finish(),
+            n => parser.push(&chunk[..n]),
+        }
+    }
+}
@@ -0,0 +1,148 @@
+use std::fmt;
+use std::marker::PhantomData;
+use std::string::FromUtf8Error;
+
+#[cfg(feature = "json")]
+use crate::body::JsonError;
+use crate::header::{HeaderMap, InvalidTypedHeader, TypedHeader};
+use crate::method::ReturnsBody;
+use crate::response::Response;
+use crate::state::{Success, Unchecked};
+
+/// A `Response` to a request with method `M`, whose body can only be read once its status has
+/// been checked
+///
+/// ```
+/// use typestate_test::method::Get;
+/// use typestate_test::{HeaderMap, Response, ResponseReader};
+///
+/// let res = Response::new(200, HeaderMap::new(), "hello");
+/// let text = ResponseReader::<_, Get>::new(res).error_for_status()?.text()?;
+/// assert_eq!(text, "hello");
+///
+/// let res = Response::new(404, HeaderMap::new(), "not found");
+/// let err = ResponseReader::<_, Get>::new(res).error_for_status().unwrap_err();
+/// assert_eq!(err.status(), 404);
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+///
+/// The body can't be read before `error_for_status()`:
+///
+/// ```compile_fail,E0599
+/// # use typestate_test::method::Get;
+/// # use typestate_test::{HeaderMap, Response, ResponseReader};
+/// let res = Response::new(500, HeaderMap::new(), "oops");
+/// ResponseReader::<_, Get>::new(res).text();
+/// ```
+///
+/// And a HEAD response never has one to read:
+///
+/// ```compile_fail,E0599
+/// # use typestate_test::method::Head;
+/// # use typestate_test::{HeaderMap, Response, ResponseReader};
+/// let res = Response::new(200, HeaderMap::new(), "");
+/// ResponseReader::<_, Head>::new(res).error_for_status()?.bytes();
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+pub struct ResponseReader<S, M> {
+    response: Response,
+    state: PhantomData<(S, M)>,
+}
+
+/// `error_for_status()` found a status other than `2xx`
+///
+/// The response is kept so its body can still be inspected.
+#[derive(Debug)]
+pub struct StatusError(Box<Response>);
+
+impl StatusError {
+    pub fn status(&self) -> u16 {
+        self.0.status()
+    }
+
+    pub fn response(&self) -> &Response {
+        &self.0
+    }
+
+    pub fn into_response(self) -> Response {
+        *self.0
+    }
+}
+
+impl fmt::Display for StatusError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "unsuccessful status {}", self.0.status())?;
+        if !self.0.reason().is_empty() {
+            write!(f, " {}", self.0.reason())?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for StatusError {}
+
+impl<S, M> fmt::Debug for ResponseReader<S, M> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("ResponseReader")
+            .field(&self.response)
+            .finish()
+    }
+}
+
+// Readable in any state
+impl<S, M> ResponseReader<S, M> {
+    pub fn status(&self) -> u16 {
+        self.response.status()
+    }
+
+    pub fn reason(&self) -> &str {
+        self.response.reason()
+    }
+
+    pub fn headers(&self) -> &HeaderMap {
+        self.response.headers()
+    }
+
+    /// Decodes the typed header `H`, or returns `None` if it isn't set
+    pub fn typed_header<H: TypedHeader>(&self) -> Result<Option<H>, InvalidTypedHeader> {
+        self.response.typed_header()
+    }
+}
+
+impl<M> ResponseReader<Unchecked, M> {
+    /// Wraps a response to a request with method `M`
+    pub fn new(response: Response) -> Self {
+        ResponseReader {
+            response,
+            state: PhantomData,
+        }
+    }
+
+    /// Moves to `Success` if the status is `2xx`, or returns the response as an error otherwise
+    pub fn error_for_status(self) -> Result<ResponseReader<Success, M>, StatusError> {
+        if !self.response.is_success() {
+            return Err(StatusError(Box::new(self.response)));
+        }
+        Ok(ResponseReader {
+            response: self.response,
+            state: PhantomData,
+        })
+    }
+}
+
+// The body is only readable once, after a successful status, and only for methods that have one
+impl<M: ReturnsBody> ResponseReader<Success, M> {
+    pub fn bytes(self) -> Vec<u8> {
+        self.response.into_body()
+    }
+
+    pub fn text(self) -> Result<String, FromUtf8Error> {
+        String::from_utf8(self.response.into_body())
+    }
+
+    /// Deserializes the body as JSON
+    #[cfg(feature = "json")]
+    pub fn json<T: serde::de::DeserializeOwned>(self) -> Result<T, JsonError> {
+        serde_json::from_slice(self.response.body()).map_err(JsonError::Serde)
+    }
+}
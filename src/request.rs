@@ -0,0 +1,238 @@
+use std::io;
+use std::time::Duration;
+
+#[cfg(feature = "json")]
+use crate::body::JsonError;
+use crate::body::Payload;
+#[cfg(feature = "serde")]
+use crate::builder::{RequestBuilder, SnapshotRef};
+use crate::curl::{self, CurlError};
+#[cfg(feature = "json")]
+use crate::har::{self, HarError, HarRequest};
+use crate::header::{
+    Accept, Authorization, ContentLength, ContentType, HeaderMap, IfNoneMatch, InvalidTypedHeader,
+    Range, TypedHeader, UserAgent,
+};
+use crate::http1::{self, InvalidRequest, SendError};
+use crate::method::Method;
+use crate::mime::Mime;
+use crate::response::Response;
+#[cfg(feature = "serde")]
+use crate::state::Checked;
+use crate::url::Url;
+
+/// A request produced by `RequestBuilder::build`
+///
+/// Requests with stream bodies are never equal, since comparing them would consume the stream.
+#[derive(Debug, PartialEq)]
+pub struct Request {
+    pub(crate) url: Url,
+    pub(crate) method: Method,
+    pub(crate) headers: HeaderMap,
+    pub(crate) body: Option<Payload>,
+    pub(crate) timeout: Option<Duration>,
+}
+
+/// Stored the same way as a `RequestBuilder` after `method_and_body()`
+///
+/// File bodies are stored by their contents and stream bodies fail to serialize. Deserializing
+/// checks the body against the method again, so a stored GET with a body is an error rather
+/// than a request that `get()` could never build.
+#[cfg(feature = "serde")]
+impl serde::Serialize for Request {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serde::Serialize::serialize(
+            &SnapshotRef {
+                url: &self.url,
+                method: &self.method,
+                headers: &self.headers,
+                body: self.body.as_ref(),
+                timeout: self.timeout,
+            },
+            serializer,
+        )
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Request {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let builder: RequestBuilder<Url, Method, Checked> =
+            serde::Deserialize::deserialize(deserializer)?;
+        Ok(builder.build())
+    }
+}
+
+/// The pieces of a `Request`, returned by `Request::into_parts`
+#[derive(Debug, PartialEq)]
+pub struct Parts {
+    pub url: Url,
+    pub method: Method,
+    pub headers: HeaderMap,
+    pub body: Option<Payload>,
+    pub timeout: Option<Duration>,
+}
+
+impl Request {
+    /// Puts a request back together from its parts
+    pub fn from_parts(parts: Parts) -> Self {
+        Request {
+            url: parts.url,
+            method: parts.method,
+            headers: parts.headers,
+            body: parts.body,
+            timeout: parts.timeout,
+        }
+    }
+
+    /// Splits the request into its parts, e.g. so middleware can modify and rebuild it
+    pub fn into_parts(self) -> Parts {
+        Parts {
+            url: self.url,
+            method: self.method,
+            headers: self.headers,
+            body: self.body,
+            timeout: self.timeout,
+        }
+    }
+
+    pub fn url(&self) -> &Url {
+        &self.url
+    }
+
+    pub fn method(&self) -> &Method {
+        &self.method
+    }
+
+    pub fn headers(&self) -> &HeaderMap {
+        &self.headers
+    }
+
+    pub fn body(&self) -> Option<&Payload> {
+        self.body.as_ref()
+    }
+
+    pub fn timeout(&self) -> Option<Duration> {
+        self.timeout
+    }
+
+    /// Sends the request over HTTP/1.1 on a new connection and reads the whole response
+    pub fn send(self) -> Result<Response, SendError> {
+        http1::send(self)
+    }
+
+    /// The request as HTTP/1.1 bytes, e.g. for debugging or golden tests
+    ///
+    /// The target is in absolute form, without the URL's userinfo or fragment. `Host` and
+    /// `Content-Length` are derived from the URL and body unless they are already set. File
+    /// bodies are read; stream bodies are an error, as reading them would consume them.
+    ///
+    /// ```
+    /// use typestate_test::{Request, RequestBuilder};
+    ///
+    /// let req = RequestBuilder::new()
+    ///     .post()
+    ///     .url("https://example.com:8443/items?draft=1")?
+    ///     .text("asdf")
+    ///     .build();
+    ///
+    /// let bytes = req.to_http1_bytes()?;
+    /// assert_eq!(
+    ///     String::from_utf8_lossy(&bytes),
+    ///     "POST https://example.com:8443/items?draft=1 HTTP/1.1\r\n\
+    ///      host: example.com:8443\r\n\
+    ///      content-type: text/plain; charset=utf-8\r\n\
+    ///      content-length: 4\r\n\
+    ///      \r\n\
+    ///      asdf"
+    /// );
+    /// assert_eq!(Request::parse_http1(&bytes)?, req);
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn to_http1_bytes(&self) -> io::Result<Vec<u8>> {
+        http1::serialize_request(self)
+    }
+
+    /// Parses a single HTTP/1.1 request, e.g. one written by `to_http1_bytes`
+    ///
+    /// Origin-form targets (`/path`) are resolved against `Host` as `http` URLs. `Host` and
+    /// `Content-Length` are dropped when they hold the values `to_http1_bytes` would derive, and
+    /// chunked bodies are decoded, so `parse_http1(to_http1_bytes(r))` gives `r` back except for
+    /// what isn't sent: the URL's userinfo and fragment, and the timeout. Like `build()`, it
+    /// rejects a body the method doesn't allow, a missing one it requires, or one without a
+    /// `Content-Type`. An empty body counts as none unless the method requires one.
+    pub fn parse_http1(bytes: &[u8]) -> Result<Request, InvalidRequest> {
+        http1::parse_request(bytes)
+    }
+
+    /// Writes the request as a `curl` command line, quoted for a POSIX shell
+    ///
+    /// ```
+    /// use typestate_test::RequestBuilder;
+    ///
+    /// let req = RequestBuilder::new()
+    ///     .url("https://example.com/notes")?
+    ///     .post()
+    ///     .text("it's done")
+    ///     .build();
+    ///
+    /// assert_eq!(
+    ///     req.to_curl()?,
+    ///     "curl -X POST https://example.com/notes \
+    ///      -H 'content-type: text/plain; charset=utf-8' --data-raw 'it'\\''s done'"
+    /// );
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn to_curl(&self) -> Result<String, CurlError> {
+        curl::to_curl(self)
+    }
+
+    /// The request as a HAR entry's `request`, e.g. to share with browser tooling
+    #[cfg(feature = "json")]
+    pub fn to_har(&self) -> Result<HarRequest, HarError> {
+        har::to_har(self)
+    }
+
+    /// Decodes the typed header `H`, or returns `None` if it isn't set
+    pub fn typed_header<H: TypedHeader>(&self) -> Result<Option<H>, InvalidTypedHeader> {
+        self.headers.typed_get()
+    }
+
+    pub fn content_type(&self) -> Result<Option<Mime>, InvalidTypedHeader> {
+        Ok(self
+            .typed_header::<ContentType>()?
+            .map(|ContentType(mime)| mime))
+    }
+
+    pub fn accept(&self) -> Result<Option<Vec<Mime>>, InvalidTypedHeader> {
+        Ok(self.typed_header::<Accept>()?.map(|Accept(types)| types))
+    }
+
+    pub fn authorization(&self) -> Result<Option<Authorization>, InvalidTypedHeader> {
+        self.typed_header()
+    }
+
+    pub fn user_agent(&self) -> Result<Option<UserAgent>, InvalidTypedHeader> {
+        self.typed_header()
+    }
+
+    pub fn content_length(&self) -> Result<Option<u64>, InvalidTypedHeader> {
+        Ok(self
+            .typed_header::<ContentLength>()?
+            .map(|ContentLength(length)| length))
+    }
+
+    pub fn if_none_match(&self) -> Result<Option<IfNoneMatch>, InvalidTypedHeader> {
+        self.typed_header()
+    }
+
+    pub fn range(&self) -> Result<Option<Range>, InvalidTypedHeader> {
+        self.typed_header()
+    }
+
+    /// Deserializes the body as JSON
+    #[cfg(feature = "json")]
+    pub fn body_json<T: serde::de::DeserializeOwned>(&self) -> Result<T, JsonError> {
+        self.body.as_ref().ok_or(JsonError::Missing)?.to_json()
+    }
+}
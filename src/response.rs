@@ -0,0 +1,60 @@
+use crate::header::{HeaderMap, InvalidTypedHeader, TypedHeader};
+
+/// A response read by `Request::send`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Response {
+    pub(crate) status: u16,
+    pub(crate) reason: String,
+    pub(crate) headers: HeaderMap,
+    pub(crate) body: Vec<u8>,
+}
+
+impl Response {
+    /// Builds a response by hand, without a reason phrase
+    pub fn new(status: u16, headers: HeaderMap, body: impl Into<Vec<u8>>) -> Self {
+        Response {
+            status,
+            reason: String::new(),
+            headers,
+            body: body.into(),
+        }
+    }
+
+    /// The status code, e.g. `404`
+    pub fn status(&self) -> u16 {
+        self.status
+    }
+
+    /// The reason phrase sent after the status code, e.g. `Not Found`
+    pub fn reason(&self) -> &str {
+        &self.reason
+    }
+
+    /// Whether the status is `2xx`
+    pub fn is_success(&self) -> bool {
+        (200..300).contains(&self.status)
+    }
+
+    pub fn headers(&self) -> &HeaderMap {
+        &self.headers
+    }
+
+    /// Decodes the typed header `H`, or returns `None` if it isn't set
+    pub fn typed_header<H: TypedHeader>(&self) -> Result<Option<H>, InvalidTypedHeader> {
+        self.headers.typed_get()
+    }
+
+    /// The decoded body, without any chunked framing
+    pub fn body(&self) -> &[u8] {
+        &self.body
+    }
+
+    /// The body as UTF-8 text
+    pub fn text(&self) -> Result<&str, std::str::Utf8Error> {
+        std::str::from_utf8(&self.body)
+    }
+
+    pub fn into_body(self) -> Vec<u8> {
+        self.body
+    }
+}
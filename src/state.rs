@@ -0,0 +1,106 @@
+//! STATES
+//!
+//! Marker types tracking which parts of a `RequestBuilder` have been set, and whether a
+//! `ResponseReader` has had its status checked
+
+use crate::body::Payload;
+use crate::method::{BodyPolicy, NoBodyAllowed, OptionalBody, RequiredBody};
+
+// URL States
+#[derive(Default, Clone)]
+pub struct MissingUrl;
+pub use crate::template::UrlTemplate;
+pub use crate::url::Url;
+
+// Method States
+#[derive(Default, Clone)]
+pub struct MissingMethod;
+// Any type implementing `HttpMethod`, e.g. `method::Get`
+
+// Body States
+#[derive(Default, Clone)]
+pub struct MissingBody;
+#[derive(Default, Clone)]
+pub struct NoBody;
+/// PUT and PATCH must carry a body, so `build()` is unavailable until `body()` is called
+#[derive(Default, Clone)]
+pub struct PendingBody;
+/// The body states without a body yet, whose headers can still be changed freely
+pub trait WithoutBody: sealed::Sealed {}
+impl WithoutBody for MissingBody {}
+impl WithoutBody for NoBody {}
+impl WithoutBody for PendingBody {}
+/// Holds the body until `build()`
+pub struct Body(pub(crate) Payload);
+/// A body, or its absence, already checked against a method only known at runtime
+pub struct Checked(pub(crate) Option<Payload>);
+
+/// The body states `body()` and the typed body setters such as `json()` accept
+pub trait AcceptsBody: sealed::Sealed {}
+impl AcceptsBody for MissingBody {}
+impl AcceptsBody for PendingBody {}
+
+// Content Type States
+/// No `Content-Type` has been set, so a request with a Body can't be built yet
+#[derive(Default, Clone)]
+pub struct MissingContentType;
+#[derive(Default, Clone)]
+pub struct ContentTypeSet;
+
+// Response States
+/// The status of a response hasn't been checked, so its body can't be read yet
+#[derive(Default, Clone)]
+pub struct Unchecked;
+/// `error_for_status()` found a `2xx` status
+#[derive(Default, Clone)]
+pub struct Success;
+
+/// Moves a body state into the state required by a method's `BodyPolicy`
+pub trait ApplyBodyPolicy<P: BodyPolicy> {
+    type Output;
+    fn apply(self) -> Self::Output;
+}
+
+// GET-like methods only accept a builder that has no body yet, so a body set
+// before the method can never be silently dropped
+impl ApplyBodyPolicy<NoBodyAllowed> for MissingBody {
+    type Output = NoBody;
+    fn apply(self) -> NoBody {
+        NoBody
+    }
+}
+
+// POST-like methods keep whatever body state they were given
+impl ApplyBodyPolicy<OptionalBody> for MissingBody {
+    type Output = MissingBody;
+    fn apply(self) -> MissingBody {
+        self
+    }
+}
+impl ApplyBodyPolicy<OptionalBody> for Body {
+    type Output = Body;
+    fn apply(self) -> Body {
+        self
+    }
+}
+
+// PUT-like methods can't be built until a body is set
+impl ApplyBodyPolicy<RequiredBody> for MissingBody {
+    type Output = PendingBody;
+    fn apply(self) -> PendingBody {
+        PendingBody
+    }
+}
+impl ApplyBodyPolicy<RequiredBody> for Body {
+    type Output = Body;
+    fn apply(self) -> Body {
+        self
+    }
+}
+
+mod sealed {
+    pub trait Sealed {}
+    impl Sealed for super::MissingBody {}
+    impl Sealed for super::NoBody {}
+    impl Sealed for super::PendingBody {}
+}
@@ -0,0 +1,183 @@
+use std::fmt;
+
+use crate::url::{encode_path_segment, ParseError, Url};
+
+/// A URL with `{name}` placeholders, e.g. `https://example.com/users/{id}/orders/{order_id}`
+///
+/// Used as the URL state of a `RequestBuilder` until every placeholder has been filled.
+#[derive(Debug, Clone)]
+pub struct UrlTemplate {
+    template: String,
+    placeholders: Vec<String>,
+    params: Vec<(String, String)>,
+}
+
+/// Why a `UrlTemplate` couldn't be parsed or expanded
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TemplateError {
+    /// A `{` has no matching `}`, or a `}` has no matching `{`
+    UnbalancedBraces,
+    /// A placeholder name is empty or contains characters other than letters, digits and `_`
+    InvalidPlaceholder(String),
+    /// The same placeholder appears more than once
+    DuplicatePlaceholder(String),
+    /// A placeholder is in the scheme or authority, where a param could change the host
+    PlaceholderBeforePath(String),
+    /// `path_param` was called with a name the template doesn't contain
+    UnknownParam(String),
+    /// `path_param` was called twice with the same name
+    DuplicateParam(String),
+    /// A placeholder was never filled by `path_param`
+    MissingParam(String),
+    /// The expanded template isn't a valid URL
+    Url(ParseError),
+}
+
+impl fmt::Display for TemplateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TemplateError::UnbalancedBraces => f.write_str("unbalanced braces in URL template"),
+            TemplateError::InvalidPlaceholder(name) => write!(f, "invalid placeholder {{{name}}}"),
+            TemplateError::DuplicatePlaceholder(name) => {
+                write!(f, "placeholder {{{name}}} appears more than once")
+            }
+            TemplateError::PlaceholderBeforePath(name) => {
+                write!(f, "placeholder {{{name}}} must come after the host")
+            }
+            TemplateError::UnknownParam(name) => {
+                write!(f, "template has no placeholder {{{name}}}")
+            }
+            TemplateError::DuplicateParam(name) => write!(f, "path param `{name}` set twice"),
+            TemplateError::MissingParam(name) => write!(f, "placeholder {{{name}}} was not filled"),
+            TemplateError::Url(err) => write!(f, "expanded template is not a valid URL: {err}"),
+        }
+    }
+}
+
+impl std::error::Error for TemplateError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            TemplateError::Url(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<ParseError> for TemplateError {
+    fn from(err: ParseError) -> Self {
+        TemplateError::Url(err)
+    }
+}
+
+impl UrlTemplate {
+    /// Parses the placeholders out of `template` and checks that it forms a valid URL once filled
+    pub fn parse(template: impl Into<String>) -> Result<Self, TemplateError> {
+        let template = template.into();
+        let mut placeholders: Vec<String> = Vec::new();
+        for segment in segments(&template)? {
+            if let Segment::Placeholder(name) = segment {
+                if placeholders.iter().any(|p| p == name) {
+                    return Err(TemplateError::DuplicatePlaceholder(name.to_string()));
+                }
+                placeholders.push(name.to_string());
+            }
+        }
+
+        // Params are encoded as path segments, which aren't valid in a scheme or host
+        if let Some(i) = template.find("://") {
+            let authority_end = template[i + 3..]
+                .find(['/', '?', '#'])
+                .map_or(template.len(), |end| i + 3 + end);
+            if let Some(start) = template[..authority_end].find('{') {
+                let name = template[start + 1..].split('}').next().unwrap_or_default();
+                return Err(TemplateError::PlaceholderBeforePath(name.to_string()));
+            }
+        }
+
+        // Catch a broken scheme or host now, rather than once every param is filled
+        let dummy = substitute(&template, |_| Some("x".to_string()))?;
+        Url::parse(&dummy)?;
+
+        Ok(UrlTemplate {
+            template,
+            placeholders,
+            params: Vec::new(),
+        })
+    }
+
+    /// The placeholder names, in the order they appear
+    pub fn placeholders(&self) -> impl Iterator<Item = &str> {
+        self.placeholders.iter().map(String::as_str)
+    }
+
+    pub(crate) fn push_param(&mut self, name: String, value: String) {
+        self.params.push((name, value));
+    }
+
+    /// Fills every placeholder with its percent-encoded param and parses the result
+    pub fn expand(&self) -> Result<Url, TemplateError> {
+        for (i, (name, _)) in self.params.iter().enumerate() {
+            if !self.placeholders.contains(name) {
+                return Err(TemplateError::UnknownParam(name.clone()));
+            }
+            if self.params[..i].iter().any(|(n, _)| n == name) {
+                return Err(TemplateError::DuplicateParam(name.clone()));
+            }
+        }
+        let expanded = substitute(&self.template, |name| {
+            self.params
+                .iter()
+                .find(|(n, _)| n == name)
+                .map(|(_, value)| encode_path_segment(value))
+        })?;
+        Ok(Url::parse(&expanded)?)
+    }
+}
+
+enum Segment<'a> {
+    Literal(&'a str),
+    Placeholder(&'a str),
+}
+
+fn segments(template: &str) -> Result<Vec<Segment<'_>>, TemplateError> {
+    let mut segments = Vec::new();
+    let mut rest = template;
+    while let Some(start) = rest.find(['{', '}']) {
+        if rest.as_bytes()[start] == b'}' {
+            return Err(TemplateError::UnbalancedBraces);
+        }
+        let end = rest[start..]
+            .find('}')
+            .ok_or(TemplateError::UnbalancedBraces)?
+            + start;
+        let name = &rest[start + 1..end];
+        if name.contains('{') {
+            return Err(TemplateError::UnbalancedBraces);
+        }
+        if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+            return Err(TemplateError::InvalidPlaceholder(name.to_string()));
+        }
+        segments.push(Segment::Literal(&rest[..start]));
+        segments.push(Segment::Placeholder(name));
+        rest = &rest[end + 1..];
+    }
+    segments.push(Segment::Literal(rest));
+    Ok(segments)
+}
+
+fn substitute(
+    template: &str,
+    mut value: impl FnMut(&str) -> Option<String>,
+) -> Result<String, TemplateError> {
+    let mut expanded = String::with_capacity(template.len());
+    for segment in segments(template)? {
+        match segment {
+            Segment::Literal(literal) => expanded.push_str(literal),
+            Segment::Placeholder(name) => match value(name) {
+                Some(value) => expanded.push_str(&value),
+                None => return Err(TemplateError::MissingParam(name.to_string())),
+            },
+        }
+    }
+    Ok(expanded)
+}
@@ -0,0 +1,345 @@
+use std::fmt;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, MutexGuard};
+
+use serde::{Deserialize, Serialize};
+
+use super::{Http1, Transport};
+use crate::body::Payload;
+use crate::header::{base64_decode, base64_encode, HeaderMap, HeaderName, HeaderValue};
+use crate::http1::SendError;
+use crate::request::Request;
+use crate::response::Response;
+
+type Redact = Box<dyn Fn(&mut HeaderMap) + Send + Sync>;
+
+/// A record/replay `Transport` backed by a JSON cassette file
+///
+/// If the file doesn't exist yet, requests go through the inner transport and every
+/// request/response pair is written to it. Once it exists, requests are answered from the file
+/// and the inner transport is never used. Requests are matched on method and URL by default;
+/// headers and a hash of the body can be added.
+///
+/// ```no_run
+/// use typestate_test::transport::Cassette;
+/// use typestate_test::Client;
+///
+/// let cassette = Cassette::open("tests/cassettes/search.json")?.redact(|headers| {
+///     headers.remove("token");
+/// });
+/// let client = Client::new("http://localhost:8080")?.transport(cassette);
+///
+/// let req = client.get("/search")?.header("Token", "zxcvasdv")?.build();
+/// let res = client.execute(req)?;
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+pub struct Cassette<T = Http1> {
+    path: PathBuf,
+    inner: T,
+    match_method: bool,
+    match_url: bool,
+    match_headers: Vec<HeaderName>,
+    match_body: bool,
+    redact: Option<Redact>,
+    state: Mutex<State>,
+}
+
+#[derive(Debug)]
+struct State {
+    recording: bool,
+    interactions: Vec<Interaction>,
+    replayed: Vec<bool>,
+}
+
+/// A cassette couldn't be read or written, or had nothing recorded for a request
+#[derive(Debug)]
+pub enum CassetteError {
+    Io(io::Error),
+    /// The cassette file isn't valid
+    Json(serde_json::Error),
+    /// A recorded response has a malformed header or body
+    Invalid(&'static str),
+    /// Nothing in the cassette matches the request
+    NoInteraction {
+        method: String,
+        url: String,
+    },
+}
+
+impl fmt::Display for CassetteError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CassetteError::Io(err) => write!(f, "cassette file error: {err}"),
+            CassetteError::Json(err) => write!(f, "invalid cassette: {err}"),
+            CassetteError::Invalid(reason) => write!(f, "invalid cassette: {reason}"),
+            CassetteError::NoInteraction { method, url } => {
+                write!(f, "no recorded interaction for {method} {url}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for CassetteError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            CassetteError::Io(err) => Some(err),
+            CassetteError::Json(err) => Some(err),
+            CassetteError::Invalid(_) | CassetteError::NoInteraction { .. } => None,
+        }
+    }
+}
+
+// On-disk format
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct File {
+    interactions: Vec<Interaction>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Interaction {
+    request: RecordedRequest,
+    response: RecordedResponse,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct RecordedRequest {
+    method: String,
+    url: String,
+    headers: Vec<(String, String)>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    body_hash: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct RecordedResponse {
+    status: u16,
+    reason: String,
+    headers: Vec<(String, String)>,
+    /// The body as text, or as base64 if it isn't valid UTF-8
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    body: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    body_base64: Option<String>,
+}
+
+impl Cassette {
+    /// Opens the cassette at `path`, recording over the network if it doesn't exist yet
+    pub fn open(path: impl Into<PathBuf>) -> Result<Self, CassetteError> {
+        Cassette::with_transport(path, Http1)
+    }
+}
+
+impl<T> Cassette<T> {
+    /// Opens the cassette at `path`, recording through `inner` if it doesn't exist yet
+    pub fn with_transport(path: impl Into<PathBuf>, inner: T) -> Result<Self, CassetteError> {
+        let path = path.into();
+        let (recording, file) = match std::fs::read(&path) {
+            Ok(bytes) => (
+                false,
+                serde_json::from_slice::<File>(&bytes).map_err(CassetteError::Json)?,
+            ),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => (true, File::default()),
+            Err(err) => return Err(CassetteError::Io(err)),
+        };
+        Ok(Cassette {
+            path,
+            inner,
+            match_method: true,
+            match_url: true,
+            match_headers: Vec::new(),
+            match_body: false,
+            redact: None,
+            state: Mutex::new(State {
+                recording,
+                replayed: vec![false; file.interactions.len()],
+                interactions: file.interactions,
+            }),
+        })
+    }
+
+    /// Whether requests are being recorded rather than replayed
+    pub fn is_recording(&self) -> bool {
+        self.lock().recording
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Sets whether the method must match; on by default
+    pub fn match_method(mut self, enabled: bool) -> Self {
+        self.match_method = enabled;
+        self
+    }
+
+    /// Sets whether the whole URL must match; on by default
+    pub fn match_url(mut self, enabled: bool) -> Self {
+        self.match_url = enabled;
+        self
+    }
+
+    /// Also requires the values of header `name` to match, after redaction
+    pub fn match_header(mut self, name: HeaderName) -> Self {
+        self.match_headers.push(name);
+        self
+    }
+
+    /// Sets whether a hash of the request body must match; off by default
+    pub fn match_body(mut self, enabled: bool) -> Self {
+        self.match_body = enabled;
+        self
+    }
+
+    /// Edits request headers before they are written to the cassette or matched, e.g. to strip
+    /// credentials
+    pub fn redact<F>(mut self, redact: F) -> Self
+    where
+        F: Fn(&mut HeaderMap) + Send + Sync + 'static,
+    {
+        self.redact = Some(Box::new(redact));
+        self
+    }
+
+    fn record_request(&self, request: &Request, body: Option<&[u8]>) -> RecordedRequest {
+        let mut headers = request.headers.clone();
+        if let Some(redact) = &self.redact {
+            redact(&mut headers);
+        }
+        RecordedRequest {
+            method: request.method.as_str().to_string(),
+            url: request.url.to_string(),
+            headers: header_pairs(&headers),
+            body_hash: body.map(|body| format!("fnv1a64:{:016x}", fnv1a64(body))),
+        }
+    }
+
+    fn matches(&self, recorded: &RecordedRequest, live: &RecordedRequest) -> bool {
+        let values = |req: &RecordedRequest, name: &HeaderName| {
+            req.headers
+                .iter()
+                .filter(|(n, _)| n == name.as_str())
+                .map(|(_, v)| v.clone())
+                .collect::<Vec<_>>()
+        };
+        (!self.match_method || recorded.method == live.method)
+            && (!self.match_url || recorded.url == live.url)
+            && (!self.match_body || recorded.body_hash == live.body_hash)
+            && self
+                .match_headers
+                .iter()
+                .all(|name| values(recorded, name) == values(live, name))
+    }
+
+    fn lock(&self) -> MutexGuard<'_, State> {
+        self.state
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+impl<T: Transport> Transport for Cassette<T> {
+    fn send(&self, mut request: Request) -> Result<Response, SendError> {
+        let body = request.body.take().map(Payload::into_bytes).transpose()?;
+        let live = self.record_request(&request, body.as_deref());
+
+        if !self.is_recording() {
+            let mut state = self.lock();
+            let matching: Vec<usize> = (0..state.interactions.len())
+                .filter(|&i| self.matches(&state.interactions[i].request, &live))
+                .collect();
+            let Some(&last) = matching.last() else {
+                return Err(SendError::Transport(Box::new(
+                    CassetteError::NoInteraction {
+                        method: live.method,
+                        url: live.url,
+                    },
+                )));
+            };
+            // Replay matching interactions in the order they were recorded, then keep
+            // answering with the last one
+            let i = matching
+                .into_iter()
+                .find(|&i| !state.replayed[i])
+                .unwrap_or(last);
+            state.replayed[i] = true;
+            return replay(&state.interactions[i].response);
+        }
+
+        request.body = body.map(Payload::bytes);
+        let response = self.inner.send(request)?;
+        let mut state = self.lock();
+        state.interactions.push(Interaction {
+            request: live,
+            response: record_response(&response),
+        });
+        let file = File {
+            interactions: state.interactions.clone(),
+        };
+        let json = serde_json::to_vec_pretty(&file).expect("cassettes always serialize");
+        std::fs::write(&self.path, json)?;
+        Ok(response)
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for Cassette<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Cassette")
+            .field("path", &self.path)
+            .field("inner", &self.inner)
+            .field("recording", &self.is_recording())
+            .finish_non_exhaustive()
+    }
+}
+
+fn header_pairs(headers: &HeaderMap) -> Vec<(String, String)> {
+    headers
+        .iter()
+        .map(|(name, value)| (name.to_string(), value.to_string()))
+        .collect()
+}
+
+fn record_response(response: &Response) -> RecordedResponse {
+    let (body, body_base64) = match std::str::from_utf8(response.body()) {
+        Ok(text) => (Some(text.to_string()), None),
+        Err(_) => (None, Some(base64_encode(response.body()))),
+    };
+    RecordedResponse {
+        status: response.status(),
+        reason: response.reason().to_string(),
+        headers: header_pairs(response.headers()),
+        body,
+        body_base64,
+    }
+}
+
+fn replay(recorded: &RecordedResponse) -> Result<Response, SendError> {
+    let invalid = |reason| SendError::Transport(Box::new(CassetteError::Invalid(reason)));
+    let mut headers = HeaderMap::new();
+    for (name, value) in &recorded.headers {
+        headers.append(
+            HeaderName::try_from(name).map_err(|_| invalid("malformed header name"))?,
+            HeaderValue::try_from(value).map_err(|_| invalid("malformed header value"))?,
+        );
+    }
+    let body = match (&recorded.body, &recorded.body_base64) {
+        (_, Some(encoded)) => {
+            base64_decode(encoded).ok_or_else(|| invalid("malformed base64 body"))?
+        }
+        (Some(text), None) => text.clone().into_bytes(),
+        (None, None) => Vec::new(),
+    };
+    Ok(Response {
+        status: recorded.status,
+        reason: recorded.reason.clone(),
+        headers,
+        body,
+    })
+}
+
+/// FNV-1a, which unlike `DefaultHasher` is stable across Rust releases
+fn fnv1a64(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, &b| {
+        (hash ^ u64::from(b)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
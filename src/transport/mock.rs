@@ -0,0 +1,216 @@
+use std::fmt;
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use super::Transport;
+use crate::body::Payload;
+use crate::http1::SendError;
+use crate::method::Method;
+use crate::request::Request;
+use crate::response::Response;
+
+/// A `Transport` that records every request and answers from scripted responses
+///
+/// Routes are tried in the order they were added and the first matching one answers. Clones
+/// share the same routes and recorded requests, so a test can keep one while a `Client` owns
+/// another.
+///
+/// ```
+/// use typestate_test::transport::{Matcher, MockTransport};
+/// use typestate_test::{Client, HeaderMap, Method, Response};
+///
+/// let mock = MockTransport::new();
+/// mock.on(
+///     Matcher::new().method(Method::GET).path("/users/42").header("token", "zxcvasdv"),
+///     Response::new(200, HeaderMap::new(), r#"{"id":42}"#),
+/// );
+///
+/// let client = Client::new("https://example.com")?.transport(mock.clone());
+/// let res = client.execute(client.get("/users/42")?.header("Token", "zxcvasdv")?.build())?;
+/// assert_eq!(res.text()?, r#"{"id":42}"#);
+///
+/// // No route matches a request without the token
+/// assert!(client.execute(client.get("/users/42")?.build()).is_err());
+/// assert_eq!(mock.requests().len(), 2);
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct MockTransport {
+    state: Arc<Mutex<State>>,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    routes: Vec<Route>,
+    requests: Vec<Request>,
+}
+
+#[derive(Debug)]
+struct Route {
+    matcher: Matcher,
+    response: Response,
+    once: bool,
+}
+
+type Predicate = Box<dyn Fn(&Request) -> bool + Send + Sync>;
+
+/// Decides which requests a scripted response answers; every condition added must hold
+#[derive(Default)]
+pub struct Matcher {
+    predicates: Vec<Predicate>,
+}
+
+/// No scripted response matched the request
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Unmatched {
+    method: Method,
+    url: String,
+}
+
+impl fmt::Display for Unmatched {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "no mock response for {} {}",
+            self.method.as_str(),
+            self.url
+        )
+    }
+}
+
+impl std::error::Error for Unmatched {}
+
+impl Matcher {
+    /// A matcher that matches every request
+    pub fn new() -> Self {
+        Matcher::default()
+    }
+
+    pub fn method(self, method: Method) -> Self {
+        self.matching(move |req| *req.method() == method)
+    }
+
+    /// Matches the whole URL, compared as a string
+    pub fn url(self, url: impl Into<String>) -> Self {
+        let url = url.into();
+        self.matching(move |req| req.url().to_string() == url)
+    }
+
+    /// Matches the URL's path, ignoring the query
+    pub fn path(self, path: impl Into<String>) -> Self {
+        let path = path.into();
+        self.matching(move |req| req.url().path() == path)
+    }
+
+    /// Matches requests where one of the values of header `name` is `value`
+    pub fn header(self, name: &str, value: impl Into<String>) -> Self {
+        let name = name.to_ascii_lowercase();
+        let value = value.into();
+        self.matching(move |req| {
+            req.headers()
+                .get_all(&name)
+                .iter()
+                .any(|v| v.as_str() == value)
+        })
+    }
+
+    /// Matches requests for which `predicate` returns true
+    pub fn matching<F>(mut self, predicate: F) -> Self
+    where
+        F: Fn(&Request) -> bool + Send + Sync + 'static,
+    {
+        self.predicates.push(Box::new(predicate));
+        self
+    }
+
+    fn matches(&self, request: &Request) -> bool {
+        self.predicates.iter().all(|predicate| predicate(request))
+    }
+}
+
+impl fmt::Debug for Matcher {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Matcher")
+            .field("predicates", &self.predicates.len())
+            .finish()
+    }
+}
+
+impl MockTransport {
+    pub fn new() -> Self {
+        MockTransport::default()
+    }
+
+    /// Answers every request matching `matcher` with `response`
+    pub fn on(&self, matcher: Matcher, response: Response) -> &Self {
+        self.push_route(matcher, response, false)
+    }
+
+    /// Answers the next request matching `matcher` with `response`, then stops matching
+    ///
+    /// Adding several for the same matcher scripts a sequence of responses.
+    pub fn once(&self, matcher: Matcher, response: Response) -> &Self {
+        self.push_route(matcher, response, true)
+    }
+
+    /// Copies of every request sent so far, in order, with stream bodies read into memory
+    pub fn requests(&self) -> Vec<Request> {
+        self.lock().requests.iter().map(copy_request).collect()
+    }
+
+    fn push_route(&self, matcher: Matcher, response: Response, once: bool) -> &Self {
+        self.lock().routes.push(Route {
+            matcher,
+            response,
+            once,
+        });
+        self
+    }
+
+    fn lock(&self) -> MutexGuard<'_, State> {
+        // Recording never leaves the state half-updated, so a panicking test doesn't poison it
+        self.state
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+impl Transport for MockTransport {
+    fn send(&self, mut request: Request) -> Result<Response, SendError> {
+        // Buffer the body so predicates can inspect it and it can be recorded
+        if let Some(body) = request.body.take() {
+            request.body = Some(Payload::bytes(body.into_bytes()?));
+        }
+
+        let mut state = self.lock();
+        let route = state
+            .routes
+            .iter()
+            .position(|route| route.matcher.matches(&request));
+        let response = match route {
+            Some(i) if state.routes[i].once => Some(state.routes.remove(i).response),
+            Some(i) => Some(state.routes[i].response.clone()),
+            None => None,
+        };
+        let unmatched = Unmatched {
+            method: request.method.clone(),
+            url: request.url.to_string(),
+        };
+        state.requests.push(request);
+        response.ok_or_else(|| SendError::Transport(Box::new(unmatched)))
+    }
+}
+
+/// Recorded bodies are always in memory, so copying never has to read a stream
+fn copy_request(request: &Request) -> Request {
+    Request {
+        url: request.url.clone(),
+        method: request.method.clone(),
+        headers: request.headers.clone(),
+        body: request
+            .body
+            .as_ref()
+            .and_then(Payload::as_bytes)
+            .map(Payload::bytes),
+        timeout: request.timeout,
+    }
+}
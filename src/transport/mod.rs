@@ -0,0 +1,32 @@
+//! Where a `Client` sends its requests
+//!
+//! `Http1` sends them over the network; `MockTransport` answers them from a script so code
+//! built on `RequestBuilder` can be tested offline. With the `json` feature, `Cassette` records
+//! real exchanges to a file once and replays them afterwards.
+
+#[cfg(feature = "json")]
+mod cassette;
+mod mock;
+
+#[cfg(feature = "json")]
+pub use cassette::{Cassette, CassetteError};
+pub use mock::{Matcher, MockTransport, Unmatched};
+
+use crate::http1::{self, SendError};
+use crate::request::Request;
+use crate::response::Response;
+
+/// Sends a built `Request` and returns its `Response`
+pub trait Transport {
+    fn send(&self, request: Request) -> Result<Response, SendError>;
+}
+
+/// Sends requests over HTTP/1.1, the same as `Request::send`
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Http1;
+
+impl Transport for Http1 {
+    fn send(&self, request: Request) -> Result<Response, SendError> {
+        http1::send(request)
+    }
+}
@@ -0,0 +1,463 @@
+use std::fmt;
+use std::net::Ipv6Addr;
+use std::str::FromStr;
+
+/// A parsed absolute URL, e.g. `https://user@example.com:8080/a/b?c=d#e`
+///
+/// ```
+/// use typestate_test::url::{ParseError, Url};
+///
+/// let url = Url::parse("HTTPS://Example.com:8080/a/b?c=d#e")?;
+/// assert_eq!(url.host(), "example.com");
+/// assert_eq!(url.port(), Some(8080));
+/// assert_eq!(url.path(), "/a/b");
+/// assert_eq!(url.query(), Some("c=d"));
+/// assert_eq!(url.to_string(), "https://example.com:8080/a/b?c=d#e");
+///
+/// assert_eq!(Url::parse("not a url"), Err(ParseError::MissingScheme));
+/// # Ok::<(), ParseError>(())
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Url {
+    scheme: String,
+    userinfo: Option<String>,
+    host: String,
+    port: Option<u16>,
+    path: String,
+    query: Option<String>,
+    fragment: Option<String>,
+}
+
+/// Why a string couldn't be parsed as a `Url`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseError {
+    /// There is no `scheme://` prefix
+    MissingScheme,
+    /// The scheme contains characters other than letters, digits, `+`, `-` and `.`
+    InvalidScheme,
+    /// The authority has no host
+    EmptyHost,
+    /// The host isn't a valid name or bracketed IPv6 address
+    InvalidHost,
+    /// The port isn't a number between 0 and 65535
+    InvalidPort,
+    /// A `%` isn't followed by two hex digits
+    InvalidPercentEncoding,
+    /// The URL contains a character that must be percent-encoded
+    InvalidCharacter(char),
+    /// A `Client` path resolved to a different scheme or authority than the base URL
+    OtherOrigin,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseError::MissingScheme => f.write_str("missing `scheme://` prefix"),
+            ParseError::InvalidScheme => f.write_str("invalid scheme"),
+            ParseError::EmptyHost => f.write_str("empty host"),
+            ParseError::InvalidHost => f.write_str("invalid host"),
+            ParseError::InvalidPort => f.write_str("invalid port"),
+            ParseError::InvalidPercentEncoding => f.write_str("invalid percent-encoding"),
+            ParseError::InvalidCharacter(c) => write!(f, "invalid character {c:?}"),
+            ParseError::OtherOrigin => f.write_str("path leaves the client's base URL origin"),
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+/// A value passed to `RequestBuilder::query_serialize` couldn't be encoded as a query string
+#[cfg(feature = "serde")]
+#[derive(Debug)]
+pub struct QueryError(pub(crate) serde_urlencoded::ser::Error);
+
+#[cfg(feature = "serde")]
+impl fmt::Display for QueryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "failed to serialize query: {}", self.0)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl std::error::Error for QueryError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.0)
+    }
+}
+
+impl Url {
+    /// Parses an absolute URL, lowercasing the scheme and host
+    pub fn parse(input: &str) -> Result<Self, ParseError> {
+        let (scheme, rest) = input.split_once("://").ok_or(ParseError::MissingScheme)?;
+        if !is_valid_scheme(scheme) {
+            return Err(ParseError::InvalidScheme);
+        }
+
+        let (rest, fragment) = match rest.split_once('#') {
+            Some((rest, fragment)) => (rest, Some(fragment)),
+            None => (rest, None),
+        };
+        let (rest, query) = match rest.split_once('?') {
+            Some((rest, query)) => (rest, Some(query)),
+            None => (rest, None),
+        };
+        let (authority, path) = match rest.find('/') {
+            Some(i) => rest.split_at(i),
+            None => (rest, ""),
+        };
+
+        let (userinfo, host_port) = match authority.rsplit_once('@') {
+            Some((userinfo, host_port)) => (Some(userinfo), host_port),
+            None => (None, authority),
+        };
+        let (host, port) = split_host_port(host_port)?;
+
+        if let Some(userinfo) = userinfo {
+            validate(userinfo, |c| {
+                is_unreserved(c) || is_sub_delim(c) || c == ':'
+            })?;
+        }
+        validate(path, is_pchar_or_slash)?;
+        if let Some(query) = query {
+            validate(query, |c| is_pchar_or_slash(c) || c == '?')?;
+        }
+        if let Some(fragment) = fragment {
+            validate(fragment, |c| is_pchar_or_slash(c) || c == '?')?;
+        }
+
+        Ok(Url {
+            scheme: scheme.to_ascii_lowercase(),
+            userinfo: userinfo.map(str::to_string),
+            host: host.to_ascii_lowercase(),
+            port,
+            path: if path.is_empty() { "/" } else { path }.to_string(),
+            query: query.map(str::to_string),
+            fragment: fragment.map(str::to_string),
+        })
+    }
+
+    pub fn scheme(&self) -> &str {
+        &self.scheme
+    }
+
+    /// `userinfo@host:port`, leaving out the parts that aren't set
+    pub fn authority(&self) -> String {
+        let mut authority = String::new();
+        if let Some(userinfo) = &self.userinfo {
+            authority.push_str(userinfo);
+            authority.push('@');
+        }
+        authority.push_str(&self.host);
+        if let Some(port) = self.port {
+            authority.push_str(&format!(":{port}"));
+        }
+        authority
+    }
+
+    pub fn userinfo(&self) -> Option<&str> {
+        self.userinfo.as_deref()
+    }
+
+    /// The host name, or an IPv6 address including its brackets
+    pub fn host(&self) -> &str {
+        &self.host
+    }
+
+    /// The port given in the URL, if any
+    pub fn port(&self) -> Option<u16> {
+        self.port
+    }
+
+    /// The port given in the URL, or the default one for `http`/`https`
+    pub fn port_or_default(&self) -> Option<u16> {
+        self.port.or(match self.scheme.as_str() {
+            "http" => Some(80),
+            "https" => Some(443),
+            _ => None,
+        })
+    }
+
+    /// The path, always starting with `/`
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    /// The query without its leading `?`
+    pub fn query(&self) -> Option<&str> {
+        self.query.as_deref()
+    }
+
+    /// The fragment without its leading `#`
+    pub fn fragment(&self) -> Option<&str> {
+        self.fragment.as_deref()
+    }
+
+    /// Resolves `reference` against this URL the way a link in a page would be (RFC 3986 §5)
+    ///
+    /// ```
+    /// use typestate_test::Url;
+    ///
+    /// let base = Url::parse("https://example.com/api/v1/")?;
+    /// assert_eq!(base.join("users?page=2")?.to_string(), "https://example.com/api/v1/users?page=2");
+    /// assert_eq!(base.join("../v2/users")?.to_string(), "https://example.com/api/v2/users");
+    /// assert_eq!(base.join("/health")?.to_string(), "https://example.com/health");
+    /// # Ok::<(), typestate_test::url::ParseError>(())
+    /// ```
+    pub fn join(&self, reference: &str) -> Result<Url, ParseError> {
+        if has_scheme(reference) {
+            return Url::parse(reference);
+        }
+        if let Some(rest) = reference.strip_prefix("//") {
+            return Url::parse(&format!("{}://{rest}", self.scheme));
+        }
+
+        let (reference, fragment) = match reference.split_once('#') {
+            Some((reference, fragment)) => (reference, Some(fragment)),
+            None => (reference, None),
+        };
+        let (path, query) = match reference.split_once('?') {
+            Some((path, query)) => (path, Some(query)),
+            None => (reference, None),
+        };
+
+        let (path, query) = if path.is_empty() {
+            (self.path.clone(), query.or(self.query.as_deref()))
+        } else if path.starts_with('/') {
+            (remove_dot_segments(path), query)
+        } else {
+            let dir = &self.path[..=self.path.rfind('/').unwrap_or(0)];
+            (remove_dot_segments(&format!("{dir}{path}")), query)
+        };
+
+        let mut joined = format!("{}://{}{path}", self.scheme, self.authority());
+        if let Some(query) = query {
+            joined.push('?');
+            joined.push_str(query);
+        }
+        if let Some(fragment) = fragment {
+            joined.push('#');
+            joined.push_str(fragment);
+        }
+        Url::parse(&joined)
+    }
+
+    /// Appends `key=value` to the query, percent-encoding both and keeping any existing pairs
+    pub fn append_query_pair(&mut self, key: &str, value: &str) {
+        let pair = format!("{}={}", form_urlencode(key), form_urlencode(value));
+        self.append_encoded_query(&pair);
+    }
+
+    /// Appends an already encoded `a=b&c=d` string to the query
+    pub(crate) fn append_encoded_query(&mut self, encoded: &str) {
+        if encoded.is_empty() {
+            return;
+        }
+        match &mut self.query {
+            Some(query) if !query.is_empty() => {
+                query.push('&');
+                query.push_str(encoded);
+            }
+            query => *query = Some(encoded.to_string()),
+        }
+    }
+}
+
+impl fmt::Display for Url {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}://{}{}", self.scheme, self.authority(), self.path)?;
+        if let Some(query) = &self.query {
+            write!(f, "?{query}")?;
+        }
+        if let Some(fragment) = &self.fragment {
+            write!(f, "#{fragment}")?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Url {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Url::parse(s)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for Url {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Url {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let url = <String as serde::Deserialize>::deserialize(deserializer)?;
+        Url::parse(&url).map_err(serde::de::Error::custom)
+    }
+}
+
+/// Anything `RequestBuilder::url` accepts
+pub trait IntoUrl {
+    fn into_url(self) -> Result<Url, ParseError>;
+}
+
+impl IntoUrl for Url {
+    fn into_url(self) -> Result<Url, ParseError> {
+        Ok(self)
+    }
+}
+
+impl IntoUrl for &str {
+    fn into_url(self) -> Result<Url, ParseError> {
+        Url::parse(self)
+    }
+}
+
+impl IntoUrl for &String {
+    fn into_url(self) -> Result<Url, ParseError> {
+        Url::parse(self)
+    }
+}
+
+impl IntoUrl for String {
+    fn into_url(self) -> Result<Url, ParseError> {
+        Url::parse(&self)
+    }
+}
+
+/// Percent-encodes `s` the way `application/x-www-form-urlencoded` does, with spaces as `+`
+pub(crate) fn form_urlencode(s: &str) -> String {
+    let mut encoded = String::with_capacity(s.len());
+    for b in s.bytes() {
+        match b {
+            b'a'..=b'z' | b'A'..=b'Z' | b'0'..=b'9' | b'*' | b'-' | b'.' | b'_' => {
+                encoded.push(b as char)
+            }
+            b' ' => encoded.push('+'),
+            _ => encoded.push_str(&format!("%{b:02X}")),
+        }
+    }
+    encoded
+}
+
+/// Percent-encodes everything but unreserved characters, so `s` can't add path segments
+pub(crate) fn encode_path_segment(s: &str) -> String {
+    let mut encoded = String::with_capacity(s.len());
+    for b in s.bytes() {
+        if is_unreserved(b as char) {
+            encoded.push(b as char);
+        } else {
+            encoded.push_str(&format!("%{b:02X}"));
+        }
+    }
+    encoded
+}
+
+fn has_scheme(reference: &str) -> bool {
+    match reference.find([':', '/', '?', '#']) {
+        Some(i) => reference.as_bytes()[i] == b':' && is_valid_scheme(&reference[..i]),
+        None => false,
+    }
+}
+
+/// Resolves `.` and `..` segments in an absolute path
+fn remove_dot_segments(path: &str) -> String {
+    let mut segments: Vec<&str> = Vec::new();
+    let mut parts = path.split('/').skip(1).peekable();
+    while let Some(part) = parts.next() {
+        let last = parts.peek().is_none();
+        match part {
+            "." | ".." => {
+                if part == ".." {
+                    segments.pop();
+                }
+                // `/a/b/..` resolves to the directory `/a/`
+                if last {
+                    segments.push("");
+                }
+            }
+            _ => segments.push(part),
+        }
+    }
+    format!("/{}", segments.join("/"))
+}
+
+fn is_valid_scheme(scheme: &str) -> bool {
+    let mut chars = scheme.chars();
+    chars.next().is_some_and(|c| c.is_ascii_alphabetic())
+        && chars.all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'))
+}
+
+fn split_host_port(host_port: &str) -> Result<(&str, Option<u16>), ParseError> {
+    let (host, port) = if host_port.starts_with('[') {
+        // IPv6 literal, e.g. `[::1]:8080`
+        let end = host_port.find(']').ok_or(ParseError::InvalidHost)?;
+        let (host, rest) = host_port.split_at(end + 1);
+        if host[1..end].parse::<Ipv6Addr>().is_err() {
+            return Err(ParseError::InvalidHost);
+        }
+        match rest {
+            "" => (host, None),
+            _ => (
+                host,
+                Some(rest.strip_prefix(':').ok_or(ParseError::InvalidHost)?),
+            ),
+        }
+    } else {
+        match host_port.split_once(':') {
+            Some((host, port)) => (host, Some(port)),
+            None => (host_port, None),
+        }
+    };
+
+    if host.is_empty() {
+        return Err(ParseError::EmptyHost);
+    }
+    if !host.starts_with('[') {
+        validate(host, |c| is_unreserved(c) || is_sub_delim(c))?;
+    }
+
+    let port = match port {
+        // `http://example.com:/` is allowed and means the default port
+        Some("") | None => None,
+        Some(port) if port.bytes().all(|b| b.is_ascii_digit()) => {
+            Some(port.parse().map_err(|_| ParseError::InvalidPort)?)
+        }
+        Some(_) => return Err(ParseError::InvalidPort),
+    };
+
+    Ok((host, port))
+}
+
+/// Checks that every character is allowed or part of a `%XX` escape
+fn validate(s: &str, allowed: impl Fn(char) -> bool) -> Result<(), ParseError> {
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        if c == '%' {
+            let escape: String = chars.by_ref().take(2).collect();
+            if escape.len() != 2 || !escape.chars().all(|c| c.is_ascii_hexdigit()) {
+                return Err(ParseError::InvalidPercentEncoding);
+            }
+        } else if !allowed(c) {
+            return Err(ParseError::InvalidCharacter(c));
+        }
+    }
+    Ok(())
+}
+
+fn is_unreserved(c: char) -> bool {
+    c.is_ascii_alphanumeric() || matches!(c, '-' | '.' | '_' | '~')
+}
+
+fn is_sub_delim(c: char) -> bool {
+    matches!(
+        c,
+        '!' | '$' | '&' | '\'' | '(' | ')' | '*' | '+' | ',' | ';' | '='
+    )
+}
+
+fn is_pchar_or_slash(c: char) -> bool {
+    is_unreserved(c) || is_sub_delim(c) || matches!(c, ':' | '@' | '/')
+}
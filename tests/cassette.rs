@@ -0,0 +1,176 @@
+#![cfg(feature = "json")]
+
+use std::io::{Read, Write};
+use std::net::TcpListener;
+use std::ops::Deref;
+use std::path::{Path, PathBuf};
+use std::thread;
+
+use typestate_test::header::ACCEPT;
+use typestate_test::http1::SendError;
+use typestate_test::transport::{Cassette, CassetteError, Matcher, MockTransport, Transport};
+use typestate_test::{Client, HeaderMap, Mime, RequestBuilder, Response};
+
+/// A fresh cassette path in the temp directory, deleted on drop
+struct CassettePath(PathBuf);
+
+impl Deref for CassettePath {
+    type Target = Path;
+
+    fn deref(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl Drop for CassettePath {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.0);
+    }
+}
+
+fn cassette_path(name: &str) -> CassettePath {
+    let path = std::env::temp_dir().join(format!(
+        "typestate-cassette-{}-{name}.json",
+        std::process::id()
+    ));
+    let _ = std::fs::remove_file(&*path);
+    CassettePath(path)
+}
+
+fn redact_token(headers: &mut HeaderMap) {
+    headers.remove("token");
+}
+
+#[test]
+fn records_once_over_the_network_then_replays() -> anyhow::Result<()> {
+    let listener = TcpListener::bind("127.0.0.1:0")?;
+    let base = format!("http://{}", listener.local_addr()?);
+    let server = thread::spawn(move || {
+        let (mut stream, _) = listener.accept().unwrap();
+        let mut request = [0; 1024];
+        let _ = stream.read(&mut request).unwrap();
+        stream
+            .write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 7\r\nX-Live: yes\r\n\r\nresults")
+            .unwrap();
+    });
+    let path = cassette_path("network");
+
+    let cassette = Cassette::open(&*path)?.redact(redact_token);
+    assert!(cassette.is_recording());
+    let client = Client::new(base.as_str())?.transport(cassette);
+    let req = client.get("/search")?.header("Token", "zxcvasdv")?.build();
+    assert_eq!(client.execute(req)?.text()?, "results");
+    server.join().unwrap();
+
+    let recorded = std::fs::read_to_string(&*path)?;
+    assert!(recorded.contains("/search"));
+    assert!(!recorded.contains("zxcvasdv"));
+    assert!(!recorded.to_ascii_lowercase().contains("token"));
+
+    // The server is gone, so this can only succeed by replaying
+    let cassette = Cassette::open(&*path)?.redact(redact_token);
+    assert!(!cassette.is_recording());
+    let client = Client::new(base.as_str())?.transport(cassette);
+    let req = client.get("/search")?.header("Token", "other")?.build();
+    let res = client.execute(req)?;
+    assert_eq!(res.status(), 200);
+    assert_eq!(res.reason(), "OK");
+    assert_eq!(res.headers().get("x-live").unwrap().as_str(), "yes");
+    assert_eq!(res.text()?, "results");
+    Ok(())
+}
+
+#[test]
+fn matching_rules_are_configurable() -> anyhow::Result<()> {
+    let mock = MockTransport::new();
+    mock.on(
+        Matcher::new().header("accept", "application/json"),
+        Response::new(200, HeaderMap::new(), "{}"),
+    )
+    .on(
+        Matcher::new().matching(|req| req.body().is_some()),
+        Response::new(201, HeaderMap::new(), "created"),
+    )
+    .on(
+        Matcher::new(),
+        Response::new(200, HeaderMap::new(), "<html>"),
+    );
+    let path = cassette_path("rules");
+    let open = || -> Result<_, CassetteError> {
+        Ok(Cassette::with_transport(&*path, mock.clone())?
+            .match_header(ACCEPT)
+            .match_body(true))
+    };
+    let get = |accept: Mime| {
+        RequestBuilder::new()
+            .get()
+            .url("https://example.com/page")
+            .unwrap()
+            .accept([accept])
+            .build()
+    };
+    let post = |body: &str| {
+        RequestBuilder::new()
+            .post()
+            .url("https://example.com/page")
+            .unwrap()
+            .text(body)
+            .build()
+    };
+
+    let recorder = open()?;
+    recorder.send(get(Mime::APPLICATION_JSON))?;
+    recorder.send(get(Mime::TEXT_HTML))?;
+    recorder.send(post("a"))?;
+
+    let replayer = open()?;
+    assert_eq!(replayer.send(get(Mime::TEXT_HTML))?.text()?, "<html>");
+    assert_eq!(replayer.send(get(Mime::APPLICATION_JSON))?.text()?, "{}");
+    assert_eq!(replayer.send(post("a"))?.status(), 201);
+
+    let err = replayer.send(post("b")).unwrap_err();
+    let SendError::Transport(err) = err else {
+        panic!("expected a cassette error, got {err:?}");
+    };
+    assert_eq!(
+        err.to_string(),
+        "no recorded interaction for POST https://example.com/page"
+    );
+
+    // Without body matching, any POST replays the recorded one
+    let lenient = Cassette::with_transport(&*path, MockTransport::new())?.match_header(ACCEPT);
+    assert_eq!(lenient.send(post("b"))?.status(), 201);
+    assert_eq!(mock.requests().len(), 3);
+    Ok(())
+}
+
+#[test]
+fn repeated_requests_replay_in_order() -> anyhow::Result<()> {
+    let mock = MockTransport::new();
+    mock.once(
+        Matcher::new(),
+        Response::new(202, HeaderMap::new(), "pending"),
+    )
+    .on(
+        Matcher::new(),
+        Response::new(200, HeaderMap::new(), vec![0xff, 0x00]),
+    );
+    let path = cassette_path("order");
+    let req = || {
+        RequestBuilder::new()
+            .get()
+            .url("https://example.com/job")
+            .unwrap()
+            .build()
+    };
+
+    let recorder = Cassette::with_transport(&*path, mock)?;
+    recorder.send(req())?;
+    recorder.send(req())?;
+
+    let replayer = Cassette::with_transport(&*path, MockTransport::new())?;
+    assert_eq!(replayer.send(req())?.status(), 202);
+    assert_eq!(replayer.send(req())?.body(), [0xff, 0x00]);
+    assert_eq!(replayer.send(req())?.body(), [0xff, 0x00]);
+    Ok(())
+}
@@ -0,0 +1,83 @@
+use typestate_test::header::HeaderValue;
+use typestate_test::url::ParseError;
+use typestate_test::{Client, Request};
+
+fn client() -> anyhow::Result<Client> {
+    Ok(Client::new("https://api.example.com/v1/")?
+        .default_header("authorization", "Bearer secret")?)
+}
+
+#[test]
+fn paths_resolve_against_the_base_url() -> anyhow::Result<()> {
+    let client = client()?;
+
+    for (path, url) in [
+        ("users/42", "https://api.example.com/v1/users/42"),
+        ("/health", "https://api.example.com/health"),
+        (
+            "../v2/users?page=2",
+            "https://api.example.com/v2/users?page=2",
+        ),
+        (
+            "https://api.example.com/other",
+            "https://api.example.com/other",
+        ),
+    ] {
+        let req = client.get(path)?.build();
+        assert_eq!(req.url().to_string(), url);
+        assert!(req.headers().contains_key("authorization"));
+    }
+    Ok(())
+}
+
+#[test]
+fn paths_cannot_leave_the_base_origin() -> anyhow::Result<()> {
+    let client = client()?;
+
+    for path in [
+        "https://evil.com/x",
+        "//evil.com/x",
+        "http://api.example.com/v1/users",
+        "https://api.example.com:8443/v1/users",
+        "https://user@api.example.com/v1/users",
+    ] {
+        assert_eq!(
+            client.get(path).err(),
+            Some(ParseError::OtherOrigin),
+            "{path}"
+        );
+    }
+    Ok(())
+}
+
+#[test]
+fn request_headers_replace_defaults() -> anyhow::Result<()> {
+    let client = client()?
+        .default_header("user-agent", "client/1")?
+        .default_header("accept", "text/html")?
+        .default_header("accept", "application/json")?;
+    let values = |req: &Request, name| -> Vec<String> {
+        req.headers()
+            .get_all(name)
+            .iter()
+            .map(|value: &HeaderValue| value.as_str().to_string())
+            .collect()
+    };
+
+    let req = client
+        .get("users")?
+        .header("User-Agent", "script/2")?
+        .header("accept", "text/plain")?
+        .header("accept", "*/*")?
+        .header("x-trace", "1")?
+        .build();
+    assert_eq!(values(&req, "user-agent"), ["script/2"]);
+    // Both defaults are replaced, and later values append as usual
+    assert_eq!(values(&req, "accept"), ["text/plain", "*/*"]);
+    assert_eq!(values(&req, "authorization"), ["Bearer secret"]);
+
+    let req = client.get("users")?.build();
+    assert_eq!(values(&req, "user-agent"), ["client/1"]);
+    assert_eq!(values(&req, "accept"), ["text/html", "application/json"]);
+    Ok(())
+}
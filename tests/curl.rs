@@ -0,0 +1,197 @@
+use std::process::Command;
+
+use typestate_test::curl::CurlError;
+use typestate_test::header::{Authorization, HeaderValue};
+use typestate_test::method::BodyRuleError;
+use typestate_test::{Method, Payload, RequestBuilder};
+
+/// Runs the arguments of `command` through `sh` and returns the words it sees
+fn shell_words(command: &str) -> Vec<String> {
+    let args = command.strip_prefix("curl ").expect("a curl command");
+    let output = Command::new("sh")
+        .arg("-c")
+        .arg(format!("printf '%s\\0' {args}"))
+        .output()
+        .expect("sh runs");
+    assert!(output.status.success());
+    String::from_utf8(output.stdout)
+        .unwrap()
+        .split_terminator('\0')
+        .map(str::to_string)
+        .collect()
+}
+
+#[test]
+fn shell_sees_the_original_values() -> anyhow::Result<()> {
+    let req = RequestBuilder::new()
+        .url("https://example.com/search?q=a%20b&x=$HOME")?
+        .post()
+        .header("X-Note", "it's \"quoted\" $(whoami) `id` \\ ~")?
+        .text("line one\nline 'two'\n@not-a-file")
+        .build();
+
+    assert_eq!(
+        shell_words(&req.to_curl()?),
+        [
+            "-X",
+            "POST",
+            "https://example.com/search?q=a%20b&x=$HOME",
+            "-H",
+            "x-note: it's \"quoted\" $(whoami) `id` \\ ~",
+            "-H",
+            "content-type: text/plain; charset=utf-8",
+            "--data-raw",
+            "line one\nline 'two'\n@not-a-file",
+        ]
+    );
+    Ok(())
+}
+
+#[test]
+fn from_curl_inverts_to_curl() -> anyhow::Result<()> {
+    let requests = [
+        RequestBuilder::new()
+            .url("https://example.com/items?page=2")?
+            .get()
+            .header("Accept", "application/json")?
+            .build(),
+        RequestBuilder::new()
+            .url("http://localhost:8080/items/1")?
+            .put()
+            .text("it's {\"a\": 1}")
+            .header("X-Trace", "a b")?
+            .build(),
+        RequestBuilder::new()
+            .url("https://example.com/")?
+            .head()
+            .build(),
+        RequestBuilder::new()
+            .url("https://example.com/upload")?
+            .post()
+            .body(Payload::file("/tmp/some file.bin"))
+            .content_type("application/octet-stream".parse()?)
+            .build(),
+    ];
+    for req in requests {
+        let command = req.to_curl()?;
+        assert_eq!(
+            RequestBuilder::from_curl(&command)?.build(),
+            req,
+            "{command}"
+        );
+    }
+    Ok(())
+}
+
+#[test]
+fn common_flags_are_understood() -> anyhow::Result<()> {
+    let req = RequestBuilder::from_curl(
+        "curl -sSL --url 'https://example.com/login' \\\n  -H \"Accept: */*\" -u alice:s3cret \\\n  -d user=alice -d remember=1",
+    )?
+    .build();
+
+    assert_eq!(req.method(), &Method::POST);
+    assert_eq!(req.url().to_string(), "https://example.com/login");
+    assert_eq!(
+        req.headers().get("accept").map(HeaderValue::as_str),
+        Some("*/*")
+    );
+    assert_eq!(
+        req.authorization()?,
+        Some(Authorization::basic("alice", "s3cret")?)
+    );
+    assert_eq!(
+        req.headers().get("content-type").map(HeaderValue::as_str),
+        Some("application/x-www-form-urlencoded")
+    );
+    assert_eq!(
+        req.body().and_then(Payload::as_str),
+        Some("user=alice&remember=1")
+    );
+
+    let req = RequestBuilder::from_curl("curl -I example.com/health")?.build();
+    assert_eq!(req.method(), &Method::HEAD);
+    assert_eq!(req.url().to_string(), "http://example.com/health");
+
+    let req =
+        RequestBuilder::from_curl("curl -XDELETE --header=X-Id:7 https://example.com/a")?.build();
+    assert_eq!(req.method(), &Method::DELETE);
+    assert_eq!(
+        req.headers().get("x-id").map(HeaderValue::as_str),
+        Some("7")
+    );
+    Ok(())
+}
+
+#[test]
+fn invalid_commands_are_rejected() {
+    let err = |command| RequestBuilder::from_curl(command).err().expect("rejected");
+
+    assert!(matches!(
+        err("curl -X GET https://example.com -d a=1"),
+        CurlError::Body(BodyRuleError::NotAllowed(Method::GET))
+    ));
+    assert!(matches!(
+        err("curl -X PUT https://example.com"),
+        CurlError::Body(BodyRuleError::Required(Method::PUT))
+    ));
+    assert!(matches!(
+        err("curl --form a=1 https://example.com"),
+        CurlError::UnknownFlag(flag) if flag == "--form"
+    ));
+    assert!(matches!(
+        err("curl https://example.com -H"),
+        CurlError::MissingValue(flag) if flag == "-H"
+    ));
+    assert!(matches!(
+        err("curl 'https://example.com"),
+        CurlError::Syntax(_)
+    ));
+    assert!(matches!(
+        err("wget https://example.com"),
+        CurlError::Syntax(_)
+    ));
+    assert!(matches!(err("curl -s"), CurlError::MissingUrl));
+    assert!(matches!(
+        err("curl -X 'GET / HTTP/1.1\r\nX-Evil: 1\r\n\r\nPOST' http://example.com/"),
+        CurlError::InvalidMethod(_)
+    ));
+    assert!(matches!(
+        err("curl -X 'GE T' http://example.com/"),
+        CurlError::InvalidMethod(method) if method == "GE T"
+    ));
+    assert!(matches!(
+        err("curl -d @body.txt https://example.com"),
+        CurlError::Unsupported(_)
+    ));
+}
+
+#[test]
+fn parsed_bodies_keep_their_content_type() -> anyhow::Result<()> {
+    let builder = RequestBuilder::from_curl("curl -d a=1 -H 'X-Debug: 1' https://example.com")?;
+    let builder = builder.remove_header("x-debug")?;
+    assert!(!builder.headers().contains_key("x-debug"));
+
+    let err = builder
+        .remove_header("Content-Type")
+        .err()
+        .expect("rejected");
+    assert_eq!(
+        err.to_string(),
+        "`content-type` can only be set with `content_type()`"
+    );
+    Ok(())
+}
+
+#[test]
+fn stream_bodies_cannot_be_exported() -> anyhow::Result<()> {
+    let req = RequestBuilder::new()
+        .url("https://example.com/upload")?
+        .post()
+        .body(Payload::stream(vec![b"chunk".to_vec()]))
+        .content_type("text/plain".parse()?)
+        .build();
+
+    assert!(matches!(req.to_curl(), Err(CurlError::UnrepresentableBody)));
+    Ok(())
+}
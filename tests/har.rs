@@ -0,0 +1,255 @@
+#![cfg(feature = "json")]
+
+use typestate_test::har::{Har, HarError, HarRequest, NameValue, Param, PostData};
+use typestate_test::header::HeaderValue;
+use typestate_test::method::BodyRuleError;
+use typestate_test::{Method, Payload, RequestBuilder};
+
+const BROWSER_HAR: &str = r#"{
+  "log": {
+    "version": "1.2",
+    "creator": {"name": "WebInspector", "version": "537.36"},
+    "entries": [
+      {
+        "startedDateTime": "2026-10-01T09:30:00.123Z",
+        "time": 42.5,
+        "request": {
+          "method": "GET",
+          "url": "https://api.example.com/items?page=2&q=a%20b",
+          "httpVersion": "http/2.0",
+          "headers": [
+            {"name": ":authority", "value": "api.example.com"},
+            {"name": ":method", "value": "GET"},
+            {"name": ":path", "value": "/items?page=2&q=a%20b"},
+            {"name": ":scheme", "value": "https"},
+            {"name": "accept", "value": "application/json"}
+          ],
+          "queryString": [{"name": "page", "value": "2"}, {"name": "q", "value": "a b"}],
+          "cookies": [],
+          "headersSize": -1,
+          "bodySize": 0
+        },
+        "response": {"status": 200, "statusText": "OK"},
+        "timings": {"send": 0.1, "wait": 40, "receive": 2.4}
+      },
+      {
+        "startedDateTime": "2026-10-01T09:30:01.000Z",
+        "time": 12,
+        "request": {
+          "method": "POST",
+          "url": "http://localhost:8080/items",
+          "httpVersion": "HTTP/1.1",
+          "headers": [
+            {"name": "Host", "value": "localhost:8080"},
+            {"name": "Content-Type", "value": "application/json"},
+            {"name": "Content-Length", "value": "13"}
+          ],
+          "queryString": [],
+          "cookies": [],
+          "headersSize": 120,
+          "bodySize": 13,
+          "postData": {"mimeType": "application/json", "text": "{\"name\":\"a\"}"}
+        },
+        "response": {"status": 201, "statusText": "Created"}
+      }
+    ]
+  }
+}"#;
+
+fn har_request(method: &str, url: &str, post_data: Option<PostData>) -> HarRequest {
+    HarRequest {
+        method: method.to_string(),
+        url: url.to_string(),
+        http_version: "HTTP/1.1".to_string(),
+        cookies: Vec::new(),
+        headers: Vec::new(),
+        query_string: Vec::new(),
+        post_data,
+        headers_size: -1,
+        body_size: -1,
+    }
+}
+
+#[test]
+fn browser_entries_become_requests() -> anyhow::Result<()> {
+    let har = Har::from_json(BROWSER_HAR)?;
+    let requests = har.requests()?;
+
+    let get = &requests[0];
+    assert_eq!(get.method(), &Method::GET);
+    assert_eq!(
+        get.url().to_string(),
+        "https://api.example.com/items?page=2&q=a%20b"
+    );
+    // Pseudo-headers are dropped
+    assert_eq!(get.headers().len(), 1);
+    assert_eq!(
+        get.headers().get("accept").map(HeaderValue::as_str),
+        Some("application/json")
+    );
+    assert!(get.body().is_none());
+
+    let post = &requests[1];
+    assert_eq!(post.method(), &Method::POST);
+    // `Host` and `Content-Length` are derived again when sending
+    assert!(!post.headers().contains_key("host"));
+    assert!(!post.headers().contains_key("content-length"));
+    assert_eq!(
+        post.body().and_then(Payload::as_str),
+        Some("{\"name\":\"a\"}")
+    );
+
+    // The rest of each entry survives a round trip through `Har`
+    assert_eq!(har.log.entries[0].extra["time"], 42.5);
+    assert_eq!(Har::from_json(&har.to_json())?, har);
+    Ok(())
+}
+
+#[test]
+fn get_with_post_data_is_rejected() -> anyhow::Result<()> {
+    let mut har = Har::from_json(BROWSER_HAR)?;
+    har.log.entries[1].request.method = "GET".to_string();
+
+    match har.requests() {
+        Err(HarError::Entry { index: 1, source }) => {
+            assert!(matches!(
+                *source,
+                HarError::Body(BodyRuleError::NotAllowed(Method::GET))
+            ));
+        }
+        other => panic!("expected entry 1 to be rejected, got {other:?}"),
+    }
+    Ok(())
+}
+
+#[test]
+fn body_rules_apply_to_single_requests() {
+    let text = |text: &str| PostData {
+        mime_type: "text/plain".to_string(),
+        text: Some(text.to_string()),
+        params: Vec::new(),
+    };
+    let err = |request| RequestBuilder::from_har(&request).err().expect("rejected");
+
+    assert!(matches!(
+        err(har_request("HEAD", "https://example.com", Some(text("")))),
+        HarError::Body(BodyRuleError::NotAllowed(Method::HEAD))
+    ));
+    assert!(matches!(
+        err(har_request("PUT", "https://example.com", None)),
+        HarError::Body(BodyRuleError::Required(Method::PUT))
+    ));
+    assert!(matches!(
+        err(har_request("GET", "example.com", None)),
+        HarError::Url(_)
+    ));
+    assert!(matches!(
+        err(har_request("GE T", "https://example.com", None)),
+        HarError::InvalidMethod(method) if method == "GE T"
+    ));
+    assert!(matches!(
+        err(har_request("GET\r\nX-Evil: 1", "https://example.com", None)),
+        HarError::InvalidMethod(_)
+    ));
+    let mut no_type = text("asdf");
+    no_type.mime_type.clear();
+    assert!(matches!(
+        err(har_request("POST", "https://example.com", Some(no_type))),
+        HarError::Body(BodyRuleError::MissingContentType)
+    ));
+}
+
+#[test]
+fn form_params_are_encoded_when_text_is_missing() -> anyhow::Result<()> {
+    let param = |name: &str, value: &str| Param {
+        name: name.to_string(),
+        value: Some(value.to_string()),
+        file_name: None,
+        content_type: None,
+    };
+    let post_data = PostData {
+        mime_type: "application/x-www-form-urlencoded".to_string(),
+        text: None,
+        params: vec![param("user", "alice"), param("note", "a&b c")],
+    };
+    let req = RequestBuilder::from_har(&har_request(
+        "POST",
+        "https://example.com/login",
+        Some(post_data.clone()),
+    ))?
+    .build();
+    assert_eq!(
+        req.body().and_then(Payload::as_str),
+        Some("user=alice&note=a%26b+c")
+    );
+
+    let multipart = PostData {
+        mime_type: "multipart/form-data; boundary=x".to_string(),
+        ..post_data
+    };
+    assert!(matches!(
+        RequestBuilder::from_har(&har_request(
+            "POST",
+            "https://example.com/upload",
+            Some(multipart)
+        )),
+        Err(HarError::Unsupported(_))
+    ));
+    Ok(())
+}
+
+#[test]
+fn exported_requests_import_unchanged() -> anyhow::Result<()> {
+    let requests = [
+        RequestBuilder::new()
+            .url("https://example.com/search?q=a+b&page=2")?
+            .get()
+            .header("Accept", "application/json")?
+            .build(),
+        RequestBuilder::new()
+            .url("http://localhost:8080/items/1")?
+            .patch()
+            .text("done")
+            .build(),
+    ];
+    let har = Har::from_requests(&requests)?;
+
+    let exported = &har.log.entries[0];
+    assert_eq!(
+        exported.request.query_string,
+        [
+            NameValue {
+                name: "q".to_string(),
+                value: "a b".to_string()
+            },
+            NameValue {
+                name: "page".to_string(),
+                value: "2".to_string()
+            },
+        ]
+    );
+    assert_eq!(exported.extra["response"]["status"], 0);
+    let started = exported.extra["startedDateTime"].as_str().unwrap();
+    assert_eq!(started.len(), "2026-10-01T09:30:00.123Z".len());
+    assert!(started.ends_with('Z'));
+
+    let post_data = har.log.entries[1].request.post_data.as_ref().unwrap();
+    assert_eq!(post_data.mime_type, "text/plain; charset=utf-8");
+    assert_eq!(har.log.entries[1].request.body_size, 4);
+
+    assert_eq!(Har::from_json(&har.to_json())?.requests()?, requests);
+    Ok(())
+}
+
+#[test]
+fn binary_bodies_cannot_be_exported() -> anyhow::Result<()> {
+    let req = RequestBuilder::new()
+        .url("https://example.com/upload")?
+        .post()
+        .body(Payload::bytes([0xff, 0xfe]))
+        .content_type("application/octet-stream".parse()?)
+        .build();
+
+    assert!(matches!(req.to_har(), Err(HarError::UnrepresentableBody)));
+    Ok(())
+}
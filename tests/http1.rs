@@ -0,0 +1,224 @@
+use std::io::{Read, Write};
+use std::net::TcpListener;
+use std::thread::{self, JoinHandle};
+
+use typestate_test::body::Payload;
+use typestate_test::http1::SendError;
+use typestate_test::{Client, Mime, RequestBuilder};
+
+/// Accepts one connection, answers it with `response` and returns the raw request it read
+fn serve(response: &'static [u8]) -> (String, JoinHandle<String>) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let base = format!("http://{}", listener.local_addr().unwrap());
+    let handle = thread::spawn(move || {
+        let (mut stream, _) = listener.accept().unwrap();
+        let mut request = Vec::new();
+        let mut chunk = [0; 1024];
+        while !request_complete(&request) {
+            let n = stream.read(&mut chunk).unwrap();
+            assert!(n > 0, "client closed before sending the whole request");
+            request.extend_from_slice(&chunk[..n]);
+        }
+        stream.write_all(response).unwrap();
+        String::from_utf8(request).unwrap()
+    });
+    (base, handle)
+}
+
+fn request_complete(request: &[u8]) -> bool {
+    let text = String::from_utf8_lossy(request);
+    let Some((head, body)) = text.split_once("\r\n\r\n") else {
+        return false;
+    };
+    let head = head.to_ascii_lowercase();
+    if head.contains("transfer-encoding: chunked") {
+        return body.ends_with("0\r\n\r\n");
+    }
+    let len = head
+        .lines()
+        .find_map(|line| line.strip_prefix("content-length: "))
+        .map_or(0, |len| len.parse().unwrap());
+    body.len() >= len
+}
+
+#[test]
+fn get_writes_request_line_and_reads_content_length_body() -> anyhow::Result<()> {
+    let (base, server) =
+        serve(b"HTTP/1.1 200 OK\r\nContent-Length: 5\r\nX-Reply: yes\r\n\r\nhello");
+
+    let res = RequestBuilder::new()
+        .get()
+        .url(format!("{base}/search?q=typestate#results"))?
+        .header("Token", "zxcvasdv")?
+        .build()
+        .send()?;
+
+    let request = server.join().unwrap();
+    assert!(request.starts_with("GET /search?q=typestate HTTP/1.1\r\n"));
+    assert!(request.contains(&format!("host: {}\r\n", base.trim_start_matches("http://"))));
+    assert!(request.contains("token: zxcvasdv\r\n"));
+    assert!(!request.contains("content-length"));
+
+    assert_eq!(res.status(), 200);
+    assert_eq!(res.reason(), "OK");
+    assert!(res.is_success());
+    assert_eq!(res.headers().get("x-reply").unwrap().as_str(), "yes");
+    assert_eq!(res.text()?, "hello");
+    Ok(())
+}
+
+#[test]
+fn chunked_response_is_decoded() -> anyhow::Result<()> {
+    let (base, server) = serve(
+        b"HTTP/1.1 100 Continue\r\n\r\n\
+          HTTP/1.1 201 Created\r\nTransfer-Encoding: chunked\r\n\r\n\
+          6;ext=1\r\nhello \r\n5\r\nworld\r\n0\r\nX-Trailer: done\r\n\r\n",
+    );
+
+    let res = RequestBuilder::new()
+        .post()
+        .url(format!("{base}/items"))?
+        .build()
+        .send()?;
+
+    let request = server.join().unwrap();
+    assert!(request.starts_with("POST /items HTTP/1.1\r\n"));
+    assert!(request.contains("content-length: 0\r\n"));
+    assert_eq!(res.status(), 201);
+    assert_eq!(res.text()?, "hello world");
+    Ok(())
+}
+
+#[test]
+fn body_is_sent_with_content_length() -> anyhow::Result<()> {
+    let (base, server) = serve(b"HTTP/1.1 204 No Content\r\n\r\n");
+
+    let res = RequestBuilder::new()
+        .put()
+        .url(format!("{base}/notes/1"))?
+        .text("asdf")
+        .build()
+        .send()?;
+
+    let request = server.join().unwrap();
+    assert!(request.starts_with("PUT /notes/1 HTTP/1.1\r\n"));
+    assert!(request.contains("content-type: text/plain; charset=utf-8\r\n"));
+    assert!(request.ends_with("content-length: 4\r\nconnection: close\r\n\r\nasdf"));
+    assert_eq!(res.status(), 204);
+    assert!(res.body().is_empty());
+    Ok(())
+}
+
+#[test]
+fn streaming_body_is_sent_chunked() -> anyhow::Result<()> {
+    let (base, server) = serve(b"HTTP/1.1 200 OK\r\nContent-Length: 0\r\n\r\n");
+
+    RequestBuilder::new()
+        .post()
+        .url(format!("{base}/upload"))?
+        .body(Payload::stream(["hello ", "world"]))
+        .content_type(Mime::TEXT_PLAIN)
+        .build()
+        .send()?;
+
+    let request = server.join().unwrap();
+    assert!(request.contains("transfer-encoding: chunked\r\n"));
+    assert!(!request.contains("content-length"));
+    let (_, body) = request.split_once("\r\n\r\n").unwrap();
+    let decoded: String = body.split("\r\n").skip(1).step_by(2).collect();
+    assert_eq!(decoded, "hello world");
+    Ok(())
+}
+
+#[test]
+fn head_response_has_no_body() -> anyhow::Result<()> {
+    let (base, server) = serve(b"HTTP/1.1 200 OK\r\nContent-Length: 1024\r\n\r\n");
+
+    let res = RequestBuilder::new()
+        .head()
+        .url(format!("{base}/large"))?
+        .build()
+        .send()?;
+
+    server.join().unwrap();
+    assert_eq!(
+        res.headers().get("content-length").unwrap().as_str(),
+        "1024"
+    );
+    assert!(res.body().is_empty());
+    Ok(())
+}
+
+#[test]
+fn body_without_framing_runs_until_close() -> anyhow::Result<()> {
+    let (base, server) = serve(b"HTTP/1.0 404 Not Found\r\n\r\nnothing here");
+
+    let client = Client::new(base)?.default_header("user-agent", "typestate")?;
+    let res = client.execute(client.get("/missing")?.build())?;
+
+    let request = server.join().unwrap();
+    assert!(request.contains("user-agent: typestate\r\n"));
+    assert_eq!(res.status(), 404);
+    assert!(!res.is_success());
+    assert_eq!(res.text()?, "nothing here");
+    Ok(())
+}
+
+#[test]
+fn https_is_rejected() -> anyhow::Result<()> {
+    let err = RequestBuilder::new()
+        .get()
+        .url("https://example.com")?
+        .build()
+        .send()
+        .unwrap_err();
+
+    assert!(matches!(err, SendError::UnsupportedScheme(scheme) if scheme == "https"));
+    Ok(())
+}
+
+#[test]
+fn malformed_response_is_an_error() -> anyhow::Result<()> {
+    let (base, server) = serve(b"HTTP/1.1 2000 OK\r\n\r\n");
+
+    let err = RequestBuilder::new()
+        .get()
+        .url(base)?
+        .build()
+        .send()
+        .unwrap_err();
+
+    server.join().unwrap();
+    assert!(matches!(err, SendError::InvalidResponse(_)));
+    Ok(())
+}
+
+#[test]
+fn builder_send_returns_a_reader_that_checks_the_status() -> anyhow::Result<()> {
+    let (base, server) = serve(b"HTTP/1.1 200 OK\r\nContent-Length: 2\r\n\r\nok");
+    let text = RequestBuilder::new()
+        .get()
+        .url(base)?
+        .send()?
+        .error_for_status()?
+        .text()?;
+    server.join().unwrap();
+    assert_eq!(text, "ok");
+
+    let (base, server) =
+        serve(b"HTTP/1.1 503 Service Unavailable\r\nContent-Length: 4\r\n\r\nbusy");
+    let err = RequestBuilder::new()
+        .post()
+        .url(base)?
+        .text("job")
+        .send()?
+        .error_for_status()
+        .unwrap_err();
+    server.join().unwrap();
+    assert_eq!(
+        err.to_string(),
+        "unsuccessful status 503 Service Unavailable"
+    );
+    assert_eq!(err.response().text()?, "busy");
+    Ok(())
+}
@@ -0,0 +1,31 @@
+use typestate_test::method::{BodyRule, HttpMethod, OptionalBody};
+use typestate_test::{Method, RequestBuilder};
+
+/// Lowercase, so not the standard GET
+struct LowerGet;
+
+impl HttpMethod for LowerGet {
+    const TOKEN: &'static str = "get";
+    type BodyPolicy = OptionalBody;
+}
+
+#[test]
+fn tokens_are_case_sensitive() -> anyhow::Result<()> {
+    let lower = Method::from_token("get")?;
+    assert_ne!(lower, Method::GET);
+    assert_eq!(lower.as_str(), "get");
+    assert_eq!(lower.body_rule(), BodyRule::Optional);
+    assert_eq!(Method::from_token("GET")?, Method::GET);
+
+    // The typed path agrees with the runtime one
+    let typed = RequestBuilder::new()
+        .url("https://example.com")?
+        .method(LowerGet)
+        .text("x")
+        .build();
+    let runtime = RequestBuilder::from_curl("curl -X get https://example.com -d x")?.build();
+    assert_eq!(typed.method(), &lower);
+    assert_eq!(runtime.method(), &lower);
+    assert!(RequestBuilder::from_curl("curl -X GET https://example.com -d x").is_err());
+    Ok(())
+}
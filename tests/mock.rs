@@ -0,0 +1,117 @@
+use typestate_test::body::Payload;
+use typestate_test::http1::SendError;
+use typestate_test::transport::{Matcher, MockTransport, Transport};
+use typestate_test::{Client, HeaderMap, Method, Mime, RequestBuilder, Response};
+
+fn ok(body: &str) -> Response {
+    Response::new(200, HeaderMap::new(), body)
+}
+
+#[test]
+fn first_matching_route_answers() -> anyhow::Result<()> {
+    let mock = MockTransport::new();
+    mock.on(Matcher::new().method(Method::POST), ok("created"))
+        .on(Matcher::new().path("/users"), ok("list"))
+        .on(Matcher::new(), Response::new(404, HeaderMap::new(), ""));
+    let client = Client::new("https://example.com")?.transport(mock.clone());
+
+    assert_eq!(
+        client
+            .execute(client.get("/users?page=2")?.build())?
+            .text()?,
+        "list"
+    );
+    assert_eq!(
+        client.execute(client.post("/users")?.build())?.text()?,
+        "created"
+    );
+    assert_eq!(client.execute(client.get("/other")?.build())?.status(), 404);
+    Ok(())
+}
+
+#[test]
+fn client_builders_send_through_the_transport() -> anyhow::Result<()> {
+    let mock = MockTransport::new();
+    mock.on(Matcher::new().path("/users"), ok("list"));
+    let client = Client::new("https://example.com")?.transport(mock.clone());
+
+    let res = client.get("/users")?.send()?.error_for_status()?;
+    assert_eq!(res.text()?, "list");
+    let res = client
+        .post("/users")?
+        .text("ferris")
+        .send()?
+        .error_for_status()?;
+    assert_eq!(res.text()?, "list");
+
+    let recorded = mock.requests();
+    assert_eq!(recorded.len(), 2);
+    assert_eq!(recorded[1].body().and_then(Payload::as_str), Some("ferris"));
+    Ok(())
+}
+
+#[test]
+fn once_routes_script_a_sequence() -> anyhow::Result<()> {
+    let mock = MockTransport::new();
+    let page = || Matcher::new().url("https://example.com/jobs/1");
+    mock.once(page(), ok("pending"))
+        .once(page(), ok("running"))
+        .on(page(), ok("done"));
+
+    let statuses = (0..4)
+        .map(|_| {
+            let req = RequestBuilder::new()
+                .get()
+                .url("https://example.com/jobs/1")?
+                .build();
+            Ok(mock.send(req)?.text()?.to_string())
+        })
+        .collect::<anyhow::Result<Vec<_>>>()?;
+
+    assert_eq!(statuses, ["pending", "running", "done", "done"]);
+    Ok(())
+}
+
+#[test]
+fn unmatched_requests_are_errors_and_still_recorded() -> anyhow::Result<()> {
+    let mock = MockTransport::new();
+    mock.on(Matcher::new().header("token", "secret"), ok(""));
+
+    let req = RequestBuilder::new()
+        .delete()
+        .url("https://example.com/users/42")?
+        .header("Token", "wrong")?
+        .build();
+    let err = mock.send(req).unwrap_err();
+
+    assert!(matches!(err, SendError::Transport(_)));
+    assert_eq!(
+        err.to_string(),
+        "no mock response for DELETE https://example.com/users/42"
+    );
+    assert_eq!(mock.requests().len(), 1);
+    Ok(())
+}
+
+#[test]
+fn stream_bodies_are_recorded_and_can_be_matched() -> anyhow::Result<()> {
+    let mock = MockTransport::new();
+    mock.on(
+        Matcher::new().matching(|req| req.body().and_then(Payload::as_str) == Some("a,b,c")),
+        ok("accepted"),
+    );
+
+    let req = RequestBuilder::new()
+        .put()
+        .url("https://example.com/upload")?
+        .body(Payload::stream(["a,", "b,", "c"]))
+        .content_type(Mime::TEXT_PLAIN)
+        .build();
+    assert_eq!(mock.send(req)?.text()?, "accepted");
+
+    let recorded = mock.requests();
+    assert_eq!(*recorded[0].method(), Method::PUT);
+    assert_eq!(recorded[0].content_type()?, Some(Mime::TEXT_PLAIN));
+    assert_eq!(recorded[0].body().and_then(Payload::as_str), Some("a,b,c"));
+    Ok(())
+}
@@ -0,0 +1,97 @@
+use typestate_test::multipart::Multipart;
+use typestate_test::{Mime, Payload};
+
+/// Encodes `form` with its random boundary replaced by `BOUNDARY`
+fn encode(form: Multipart) -> anyhow::Result<String> {
+    let boundary = form.boundary().to_string();
+    let bytes = form.into_payload().into_bytes()?;
+    Ok(String::from_utf8(bytes)?.replace(&boundary, "BOUNDARY"))
+}
+
+#[test]
+fn in_memory_parts_are_encoded() -> anyhow::Result<()> {
+    let form = Multipart::new().text("title", "holiday\r\nphotos").file(
+        "photo",
+        "beach.png",
+        Mime::APPLICATION_OCTET_STREAM,
+        b"PNG".to_vec(),
+    );
+    let boundary = form.boundary().to_string();
+    let payload = form.into_payload();
+    assert!(payload.as_bytes().is_some());
+
+    let encoded = String::from_utf8(payload.into_bytes()?)?.replace(&boundary, "BOUNDARY");
+    assert_eq!(
+        encoded,
+        "--BOUNDARY\r\n\
+         Content-Disposition: form-data; name=\"title\"\r\n\
+         \r\n\
+         holiday\r\nphotos\r\n\
+         --BOUNDARY\r\n\
+         Content-Disposition: form-data; name=\"photo\"; filename=\"beach.png\"\r\n\
+         Content-Type: application/octet-stream\r\n\
+         \r\n\
+         PNG\r\n\
+         --BOUNDARY--\r\n"
+    );
+    assert_eq!(encode(Multipart::new())?, "--BOUNDARY--\r\n");
+    Ok(())
+}
+
+#[test]
+fn names_and_filenames_cannot_break_out_of_their_quotes() -> anyhow::Result<()> {
+    let form = Multipart::new().file("up\"load", "a\".txt\r\nX-Evil: 1", Mime::TEXT_PLAIN, "x");
+    assert_eq!(
+        encode(form)?,
+        "--BOUNDARY\r\n\
+         Content-Disposition: form-data; name=\"up%22load\"; filename=\"a%22.txt%0D%0AX-Evil: 1\"\r\n\
+         Content-Type: text/plain\r\n\
+         \r\n\
+         x\r\n\
+         --BOUNDARY--\r\n"
+    );
+    Ok(())
+}
+
+#[test]
+fn file_and_stream_parts_are_read_when_encoded() -> anyhow::Result<()> {
+    let path = std::env::temp_dir().join(format!("multipart-part-{}", std::process::id()));
+    std::fs::write(&path, "from disk")?;
+    let form = Multipart::new()
+        .file("doc", "doc.txt", Mime::TEXT_PLAIN, Payload::file(&path))
+        .file(
+            "log",
+            "log.txt",
+            Mime::TEXT_PLAIN,
+            Payload::stream(["chunk one, ", "chunk two"]),
+        );
+    let boundary = form.boundary().to_string();
+    let payload = form.into_payload();
+    assert!(payload.as_bytes().is_none());
+
+    let encoded = String::from_utf8(payload.into_bytes()?)?.replace(&boundary, "BOUNDARY");
+    std::fs::remove_file(&path)?;
+    assert_eq!(
+        encoded,
+        "--BOUNDARY\r\n\
+         Content-Disposition: form-data; name=\"doc\"; filename=\"doc.txt\"\r\n\
+         Content-Type: text/plain\r\n\
+         \r\n\
+         from disk\r\n\
+         --BOUNDARY\r\n\
+         Content-Disposition: form-data; name=\"log\"; filename=\"log.txt\"\r\n\
+         Content-Type: text/plain\r\n\
+         \r\n\
+         chunk one, chunk two\r\n\
+         --BOUNDARY--\r\n"
+    );
+    Ok(())
+}
+
+#[test]
+fn boundaries_are_unique() {
+    let a = Multipart::new();
+    let b = Multipart::new();
+    assert_ne!(a.boundary(), b.boundary());
+    assert_eq!(a.content_type().param("boundary"), Some(a.boundary()));
+}
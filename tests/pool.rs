@@ -0,0 +1,199 @@
+#![cfg(feature = "tokio")]
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::net::TcpListener;
+use typestate_test::body::Payload;
+use typestate_test::http1::SendError;
+use typestate_test::pool::Pool;
+use typestate_test::{Client, Mime, RequestBuilder};
+
+/// How the stub server treats each connection
+#[derive(Clone, Copy)]
+enum Mode {
+    /// Answer any number of requests, echoing the body back with `Content-Length`
+    KeepAlive,
+    /// Answer one request with `Connection: close`, then hang up
+    Close,
+    /// Answer one request as if keeping the connection alive, then hang up anyway
+    HangUp,
+    /// Read the request and never answer
+    Silent,
+}
+
+/// Serves connections in the background, returning the base URL and a count of accepted connections
+async fn serve(mode: Mode) -> (String, Arc<AtomicUsize>) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let base = format!("http://{}", listener.local_addr().unwrap());
+    let accepted = Arc::new(AtomicUsize::new(0));
+    let counter = accepted.clone();
+    tokio::spawn(async move {
+        loop {
+            let (stream, _) = listener.accept().await.unwrap();
+            let conn = counter.fetch_add(1, Ordering::SeqCst) + 1;
+            tokio::spawn(async move {
+                let mut stream = BufReader::new(stream);
+                let mut served = 0;
+                while let Some((target, body)) = read_request(&mut stream).await {
+                    served += 1;
+                    let reply = format!("conn={conn} n={served} {target} {body}");
+                    let connection = match mode {
+                        Mode::Close => "close",
+                        Mode::Silent => {
+                            tokio::time::sleep(Duration::from_secs(60)).await;
+                            return;
+                        }
+                        _ => "keep-alive",
+                    };
+                    let response = format!(
+                        "HTTP/1.1 200 OK\r\nConnection: {connection}\r\nContent-Length: {}\r\n\r\n{reply}",
+                        reply.len()
+                    );
+                    stream
+                        .get_mut()
+                        .write_all(response.as_bytes())
+                        .await
+                        .unwrap();
+                    if !matches!(mode, Mode::KeepAlive) {
+                        return;
+                    }
+                }
+            });
+        }
+    });
+    (base, accepted)
+}
+
+/// Reads one request, returning its target and body, or `None` once the client hangs up
+async fn read_request(stream: &mut BufReader<tokio::net::TcpStream>) -> Option<(String, String)> {
+    let mut line = String::new();
+    if stream.read_line(&mut line).await.ok()? == 0 {
+        return None;
+    }
+    let target = line.split(' ').nth(1)?.to_string();
+    let mut len = 0;
+    loop {
+        line.clear();
+        stream.read_line(&mut line).await.ok()?;
+        if line == "\r\n" {
+            break;
+        }
+        if let Some(value) = line.to_ascii_lowercase().strip_prefix("content-length:") {
+            len = value.trim().parse().ok()?;
+        }
+    }
+    let mut body = vec![0; len];
+    stream.read_exact(&mut body).await.ok()?;
+    Some((target, String::from_utf8(body).ok()?))
+}
+
+#[tokio::test]
+async fn keep_alive_connections_are_reused() -> anyhow::Result<()> {
+    let (base, accepted) = serve(Mode::KeepAlive).await;
+    let pool = Pool::new();
+
+    for n in 1..=3 {
+        let req = RequestBuilder::new()
+            .get()
+            .url(format!("{base}/ping"))?
+            .build();
+        let res = pool.send(req).await?;
+        assert_eq!(res.text()?, format!("conn=1 n={n} /ping "));
+        assert_eq!(pool.idle_connections(), 1);
+    }
+    assert_eq!(accepted.load(Ordering::SeqCst), 1);
+    Ok(())
+}
+
+#[tokio::test]
+async fn concurrent_requests_open_separate_connections() -> anyhow::Result<()> {
+    let (base, accepted) = serve(Mode::KeepAlive).await;
+    let client = Client::new(base)?;
+
+    let (a, b) = tokio::join!(
+        client.execute_async(client.get("/a")?.build()),
+        client.execute_async(client.get("/b")?.build()),
+    );
+    assert!(a?.is_success() && b?.is_success());
+    assert_eq!(accepted.load(Ordering::SeqCst), 2);
+
+    let res = client.execute_async(client.get("/c")?.build()).await?;
+    assert!(res.text()?.ends_with("n=2 /c "));
+    assert_eq!(accepted.load(Ordering::SeqCst), 2);
+    Ok(())
+}
+
+#[tokio::test]
+async fn closed_connections_are_not_pooled() -> anyhow::Result<()> {
+    let (base, accepted) = serve(Mode::Close).await;
+    let pool = Pool::new();
+
+    for _ in 0..2 {
+        let req = RequestBuilder::new().get().url(base.as_str())?.build();
+        assert!(pool.send(req).await?.is_success());
+        assert_eq!(pool.idle_connections(), 0);
+    }
+    assert_eq!(accepted.load(Ordering::SeqCst), 2);
+    Ok(())
+}
+
+#[tokio::test]
+async fn stale_connection_is_retried_on_a_new_one() -> anyhow::Result<()> {
+    let (base, accepted) = serve(Mode::HangUp).await;
+    let pool = Pool::new();
+
+    let req = RequestBuilder::new().get().url(base.as_str())?.build();
+    pool.send(req).await?;
+    assert_eq!(pool.idle_connections(), 1);
+    // Give the server time to hang up the pooled connection
+    tokio::time::sleep(Duration::from_millis(50)).await;
+
+    let req = RequestBuilder::new()
+        .put()
+        .url(format!("{base}/notes/1"))?
+        .body(Payload::stream(["streamed ", "body"]))
+        .content_type(Mime::TEXT_PLAIN)
+        .build();
+    let res = pool.send(req).await?;
+    assert_eq!(res.text()?, "conn=2 n=1 /notes/1 streamed body");
+    assert_eq!(accepted.load(Ordering::SeqCst), 2);
+    Ok(())
+}
+
+#[tokio::test]
+async fn stale_connection_is_not_retried_after_sending_a_post() -> anyhow::Result<()> {
+    let (base, accepted) = serve(Mode::HangUp).await;
+    let pool = Pool::new();
+
+    let req = RequestBuilder::new().get().url(base.as_str())?.build();
+    pool.send(req).await?;
+    tokio::time::sleep(Duration::from_millis(50)).await;
+
+    // The server might have acted on the POST before hanging up, so it must not be sent twice
+    let req = RequestBuilder::new()
+        .post()
+        .url(format!("{base}/orders"))?
+        .text("one order")
+        .build();
+    assert!(pool.send(req).await.is_err());
+    assert_eq!(accepted.load(Ordering::SeqCst), 1);
+    Ok(())
+}
+
+#[tokio::test]
+async fn timeout_covers_the_whole_exchange() -> anyhow::Result<()> {
+    let (base, _) = serve(Mode::Silent).await;
+
+    let req = RequestBuilder::new()
+        .get()
+        .url(base.as_str())?
+        .timeout(Duration::from_millis(100))
+        .build();
+    let err = Pool::new().send(req).await.unwrap_err();
+
+    assert!(matches!(err, SendError::Io(err) if err.kind() == std::io::ErrorKind::TimedOut));
+    Ok(())
+}
@@ -0,0 +1,86 @@
+#![cfg(feature = "serde")]
+
+use std::collections::BTreeMap;
+
+use serde::Serialize;
+use typestate_test::RequestBuilder;
+
+#[derive(Serialize)]
+struct Search<'a> {
+    a: u32,
+    b: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    page: Option<u32>,
+}
+
+#[test]
+fn serialized_pairs_are_appended() -> anyhow::Result<()> {
+    let search = Search {
+        a: 1,
+        b: "c d",
+        page: None,
+    };
+    let req = RequestBuilder::new()
+        .get()
+        .url("https://example.com/search?x=1")?
+        .query_serialize(&search)?
+        .build();
+    assert_eq!(req.url().query(), Some("x=1&a=1&b=c+d"));
+
+    let map = BTreeMap::from([("q", "a&b"), ("lang", "en")]);
+    let req = RequestBuilder::new()
+        .get()
+        .url("https://example.com/search")?
+        .query_serialize(&map)?
+        .query_serialize(&[("tag", "x"), ("tag", "y")])?
+        .build();
+    assert_eq!(req.url().query(), Some("lang=en&q=a%26b&tag=x&tag=y"));
+
+    // Nothing to add leaves the URL alone
+    let req = RequestBuilder::new()
+        .get()
+        .url("https://example.com/search")?
+        .query_serialize(&BTreeMap::<String, String>::new())?
+        .build();
+    assert_eq!(req.url().query(), None);
+    Ok(())
+}
+
+#[derive(Serialize)]
+struct Nested {
+    filter: Search<'static>,
+}
+
+#[derive(Serialize)]
+struct WithList {
+    tags: Vec<&'static str>,
+}
+
+#[test]
+fn non_maps_and_nested_values_are_errors() -> anyhow::Result<()> {
+    let builder = || {
+        RequestBuilder::new()
+            .get()
+            .url("https://example.com/search")
+    };
+
+    assert!(builder()?.query_serialize(&42).is_err());
+    assert!(builder()?.query_serialize("a=1").is_err());
+    assert!(builder()?
+        .query_serialize(&Nested {
+            filter: Search {
+                a: 1,
+                b: "x",
+                page: Some(2),
+            },
+        })
+        .is_err());
+    let err = builder()?
+        .query_serialize(&WithList {
+            tags: vec!["a", "b"],
+        })
+        .err()
+        .expect("rejected");
+    assert!(err.to_string().starts_with("failed to serialize query"));
+    Ok(())
+}
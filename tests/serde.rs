@@ -0,0 +1,161 @@
+#![cfg(feature = "serde")]
+
+use std::time::Duration;
+
+use typestate_test::header::HeaderValue;
+use typestate_test::method::BodyRuleError;
+use typestate_test::state::Checked;
+use typestate_test::{HeaderMap, Method, Mime, Payload, Request, RequestBuilder, Url};
+
+#[test]
+fn stored_format_is_stable() -> anyhow::Result<()> {
+    let req = RequestBuilder::new()
+        .url("https://example.com/items")?
+        .post()
+        .body("[1,2,3]")
+        .content_type(Mime::APPLICATION_JSON)
+        .build();
+
+    let stored = serde_json::to_string(&req)?;
+    assert_eq!(
+        stored,
+        r#"{"url":"https://example.com/items","method":"POST","headers":[["content-type","application/json"]],"body":{"text":"[1,2,3]"}}"#
+    );
+    assert_eq!(serde_json::from_str::<Request>(&stored)?, req);
+    Ok(())
+}
+
+#[test]
+fn requests_round_trip() -> anyhow::Result<()> {
+    let requests = [
+        RequestBuilder::new()
+            .url("https://example.com/search?q=a+b")?
+            .get()
+            .header("Accept", "text/html")?
+            .header("accept", "application/json")?
+            .header("X-Trace", "1")?
+            .timeout(Duration::from_millis(1500))
+            .build(),
+        RequestBuilder::new()
+            .url("http://localhost:8080/blob")?
+            .put()
+            .body(Payload::bytes([0, 159, 146, 150]))
+            .content_type("application/octet-stream".parse()?)
+            .build(),
+        RequestBuilder::from_curl("curl -X PROPFIND https://example.com/files -H 'Depth: 1'")?
+            .build(),
+    ];
+    for req in requests {
+        let stored = serde_json::to_string(&req)?;
+        assert_eq!(serde_json::from_str::<Request>(&stored)?, req, "{stored}");
+    }
+    Ok(())
+}
+
+#[test]
+fn body_rules_are_checked_again() {
+    let err = |json: &str| {
+        serde_json::from_str::<Request>(json)
+            .unwrap_err()
+            .to_string()
+    };
+
+    assert!(err(
+        r#"{"url":"https://example.com","method":"GET","headers":[["content-type","text/plain"]],"body":{"text":"a"}}"#
+    )
+    .contains(&BodyRuleError::NotAllowed(Method::GET).to_string()));
+    assert!(
+        err(r#"{"url":"https://example.com","method":"PATCH","headers":[]}"#)
+            .contains(&BodyRuleError::Required(Method::PATCH).to_string())
+    );
+    assert!(
+        err(r#"{"url":"https://example.com","method":"POST","body":{"text":"a"}}"#)
+            .contains(&BodyRuleError::MissingContentType.to_string())
+    );
+}
+
+#[test]
+fn invalid_parts_are_rejected() {
+    let parses = |json: &str| serde_json::from_str::<Request>(json).is_ok();
+
+    assert!(parses(r#"{"url":"https://example.com","method":"GET"}"#));
+    assert!(!parses(r#"{"url":"example.com","method":"GET"}"#));
+    assert!(!parses(r#"{"url":"https://example.com","method":"GE T"}"#));
+    assert!(!parses(
+        r#"{"url":"https://example.com","method":"GET","headers":[["x name","1"]]}"#
+    ));
+    assert!(!parses(
+        r#"{"url":"https://example.com","method":"GET","headers":[["x-a","1\r\nInjected: yes"]]}"#
+    ));
+}
+
+#[test]
+fn builder_snapshots_can_be_finished_later() -> anyhow::Result<()> {
+    let builder = RequestBuilder::from_curl("curl -d a=1 https://example.com/form")?;
+    let stored = serde_json::to_string(&builder)?;
+
+    let restored: RequestBuilder<Url, Method, Checked> = serde_json::from_str(&stored)?;
+    let req = restored.header("X-Retry", "1")?.build();
+
+    assert_eq!(req.method(), &Method::POST);
+    assert_eq!(req.body().and_then(Payload::as_str), Some("a=1"));
+    assert_eq!(
+        req.headers().get("x-retry").map(HeaderValue::as_str),
+        Some("1")
+    );
+    Ok(())
+}
+
+#[test]
+fn header_maps_keep_repeated_names() -> anyhow::Result<()> {
+    let headers: HeaderMap =
+        serde_json::from_str(r#"[["Accept","text/html"],["X-A","1"],["accept","*/*"]]"#)?;
+
+    assert_eq!(headers.get_all("accept").len(), 2);
+    assert_eq!(
+        serde_json::to_string(&headers)?,
+        r#"[["accept","text/html"],["accept","*/*"],["x-a","1"]]"#
+    );
+    Ok(())
+}
+
+#[test]
+fn file_bodies_are_stored_by_contents() -> anyhow::Result<()> {
+    let path = std::env::temp_dir().join(format!("serde-file-body-{}", std::process::id()));
+    std::fs::write(&path, "queued contents")?;
+    let req = RequestBuilder::new()
+        .url("http://localhost:8080/upload")?
+        .post()
+        .body(Payload::file(&path))
+        .content_type("text/plain".parse()?)
+        .build();
+
+    let stored = serde_json::to_string(&req)?;
+    std::fs::remove_file(&path)?;
+    assert!(!stored.contains(&*path.to_string_lossy()));
+    let loaded: Request = serde_json::from_str(&stored)?;
+    assert_eq!(
+        loaded.body().and_then(Payload::as_str),
+        Some("queued contents")
+    );
+
+    // A stored path would make the loading host upload its own file
+    assert!(serde_json::from_str::<Request>(
+        r#"{"url":"http://localhost:8080/upload","method":"POST","headers":[["content-type","text/plain"]],"body":{"file":"/etc/passwd"}}"#
+    )
+    .is_err());
+    Ok(())
+}
+
+#[test]
+fn stream_bodies_cannot_be_stored() -> anyhow::Result<()> {
+    let req = RequestBuilder::new()
+        .url("https://example.com/upload")?
+        .post()
+        .body(Payload::stream(["chunk"]))
+        .content_type("text/plain".parse()?)
+        .build();
+
+    assert!(serde_json::to_string(&req).is_err());
+    Ok(())
+}
@@ -0,0 +1,231 @@
+use std::time::Duration;
+
+use proptest::prelude::*;
+use typestate_test::header::{HeaderName, HeaderValue, CONTENT_TYPE};
+use typestate_test::http1::InvalidRequest;
+use typestate_test::method::BodyRule;
+use typestate_test::{HeaderMap, Method, Parts, Payload, Request, Url};
+
+fn method() -> impl Strategy<Value = Method> {
+    prop_oneof![
+        Just(Method::GET),
+        Just(Method::POST),
+        Just(Method::PUT),
+        Just(Method::PATCH),
+        Just(Method::DELETE),
+        Just(Method::HEAD),
+        Just(Method::OPTIONS),
+        Just(Method::TRACE),
+        "[A-Z]{3,10}".prop_map(|token| Method::from_token(&token).unwrap()),
+    ]
+}
+
+/// A URL, and the same URL without the userinfo and fragment that aren't sent on the wire
+fn url() -> impl Strategy<Value = (Url, Url)> {
+    (
+        proptest::option::of("[a-z]{1,8}(:[a-z0-9]{0,8})?"),
+        proptest::option::of("[a-z0-9/?]{0,8}"),
+        prop_oneof![Just("http"), Just("https")],
+        "[a-z][a-z0-9-]{0,10}(\\.[a-z]{2,5}){0,2}",
+        proptest::option::of(1..=u16::MAX),
+        proptest::collection::vec("[A-Za-z0-9_~-]{1,8}", 0..4),
+        proptest::option::of(
+            "[a-z0-9]{1,5}=([a-z0-9+]|%[0-9A-F]{2}){0,8}(&[a-z0-9]{1,5}=[a-z0-9]{0,5}){0,2}",
+        ),
+    )
+        .prop_map(
+            |(userinfo, fragment, scheme, host, port, segments, query)| {
+                let mut url = format!("{scheme}://{host}");
+                if let Some(port) = port {
+                    url.push_str(&format!(":{port}"));
+                }
+                url.push('/');
+                url.push_str(&segments.join("/"));
+                if let Some(query) = query {
+                    url.push_str(&format!("?{query}"));
+                }
+                let sent = Url::parse(&url).unwrap();
+                if let Some(userinfo) = userinfo {
+                    url.insert_str(scheme.len() + 3, &format!("{userinfo}@"));
+                }
+                if let Some(fragment) = fragment {
+                    url.push_str(&format!("#{fragment}"));
+                }
+                (Url::parse(&url).unwrap(), sent)
+            },
+        )
+}
+
+/// Any headers except the ones serialization derives or that change the framing
+fn headers() -> impl Strategy<Value = HeaderMap> {
+    let name = "[a-z][a-z0-9-]{0,15}".prop_filter("derived or framing header", |name| {
+        !matches!(
+            name.as_str(),
+            "host" | "content-length" | "transfer-encoding" | "content-type"
+        )
+    });
+    // Leading and trailing whitespace isn't part of a header value on the wire
+    let value = "([!-~]([ -~]{0,20}[!-~])?)?";
+    proptest::collection::vec((name, value), 0..6).prop_map(|pairs| {
+        pairs
+            .into_iter()
+            .map(|(name, value)| {
+                (
+                    HeaderName::try_from(name).unwrap(),
+                    HeaderValue::try_from(value).unwrap(),
+                )
+            })
+            .collect()
+    })
+}
+
+/// Requests whose body follows their method's `BodyRule` and has a `Content-Type`, since others
+/// can't be parsed back, together with the request parsing gives back
+fn request() -> impl Strategy<Value = (Request, Request)> {
+    method()
+        .prop_flat_map(|method| {
+            let body = match method.body_rule() {
+                BodyRule::NotAllowed => Just(None).boxed(),
+                // An empty body is read back as none
+                BodyRule::Optional => {
+                    proptest::option::of(proptest::collection::vec(any::<u8>(), 1..64)).boxed()
+                }
+                BodyRule::Required => proptest::collection::vec(any::<u8>(), 0..64)
+                    .prop_map(Some)
+                    .boxed(),
+            };
+            let timeout = proptest::option::of((1..60_000u64).prop_map(Duration::from_millis));
+            (Just(method), url(), headers(), body, timeout)
+        })
+        .prop_map(|(method, (url, sent_url), mut headers, body, timeout)| {
+            if body.is_some() {
+                headers.insert(
+                    CONTENT_TYPE,
+                    HeaderValue::from_static("application/octet-stream"),
+                );
+            }
+            let parsed = Request::from_parts(Parts {
+                url: sent_url,
+                method: method.clone(),
+                headers: headers.clone(),
+                body: body.clone().map(Payload::bytes),
+                timeout: None,
+            });
+            let req = Request::from_parts(Parts {
+                url,
+                method,
+                headers,
+                body: body.map(Payload::bytes),
+                timeout,
+            });
+            (req, parsed)
+        })
+}
+
+proptest! {
+    #[test]
+    fn parse_inverts_serialize((req, parsed) in request()) {
+        // Only the userinfo, fragment and timeout are lost
+        let bytes = req.to_http1_bytes().unwrap();
+        prop_assert_eq!(Request::parse_http1(&bytes).unwrap(), parsed);
+    }
+
+    #[test]
+    fn serialize_is_stable_after_a_round_trip((req, _) in request()) {
+        let bytes = req.to_http1_bytes().unwrap();
+        let reparsed = Request::parse_http1(&bytes).unwrap();
+        prop_assert_eq!(reparsed.to_http1_bytes().unwrap(), bytes);
+    }
+
+    #[test]
+    fn truncated_input_is_rejected((req, _) in request(), cut in any::<prop::sample::Index>()) {
+        let bytes = req.to_http1_bytes().unwrap();
+        let cut = cut.index(bytes.len());
+        prop_assert!(Request::parse_http1(&bytes[..cut]).is_err());
+    }
+}
+
+#[test]
+fn origin_form_resolves_against_host() -> anyhow::Result<()> {
+    let req = Request::parse_http1(
+        b"GET /search?q=typestate HTTP/1.1\r\nHost: example.com:8080\r\nToken: zxcvasdv\r\n\r\n",
+    )?;
+
+    assert_eq!(
+        req.url().to_string(),
+        "http://example.com:8080/search?q=typestate"
+    );
+    assert_eq!(*req.method(), Method::GET);
+    assert!(!req.headers().contains_key("host"));
+    assert_eq!(req.headers().get("token").unwrap().as_str(), "zxcvasdv");
+    assert!(req.body().is_none());
+    Ok(())
+}
+
+#[test]
+fn explicit_headers_are_kept_when_they_differ_from_derived_ones() -> anyhow::Result<()> {
+    let bytes = b"POST http://example.com/ HTTP/1.1\r\nHost: proxy.local\r\n\
+                  Content-Type: text/plain\r\nTransfer-Encoding: chunked\r\n\r\n3\r\nabc\r\n2\r\nde\r\n0\r\n\r\n";
+    let req = Request::parse_http1(bytes)?;
+
+    assert_eq!(req.headers().get("host").unwrap().as_str(), "proxy.local");
+    assert!(!req.headers().contains_key("transfer-encoding"));
+    assert_eq!(req.body().and_then(Payload::as_str), Some("abcde"));
+    assert!(String::from_utf8(req.to_http1_bytes()?)?.contains("host: proxy.local\r\n"));
+    Ok(())
+}
+
+#[test]
+fn malformed_requests_are_rejected() {
+    let cases: [&[u8]; 8] = [
+        b"GET http://example.com/ HTTP/1.0\r\n\r\n",
+        b"GET /no-host HTTP/1.1\r\n\r\n",
+        b"G(T http://example.com/ HTTP/1.1\r\n\r\n",
+        b"POST http://example.com/ HTTP/1.1\r\ncontent-length: 5\r\n\r\nabc",
+        b"POST http://example.com/ HTTP/1.1\r\n\r\nbody",
+        b"GET http://example.com/ HTTP/1.1\r\ncontent-length: 3\r\n\r\nabc",
+        b"PUT http://example.com/ HTTP/1.1\r\n\r\n",
+        b"POST http://example.com/ HTTP/1.1\r\ncontent-length: 3\r\n\r\nabc",
+    ];
+    for bytes in cases {
+        let err: InvalidRequest = Request::parse_http1(bytes).unwrap_err();
+        assert!(err.to_string().starts_with("invalid HTTP/1.1 request"));
+    }
+}
+
+#[test]
+fn empty_bodies_count_as_none() -> anyhow::Result<()> {
+    let req =
+        Request::parse_http1(b"GET http://example.com/ HTTP/1.1\r\ncontent-length: 0\r\n\r\n")?;
+    assert!(req.body().is_none());
+    assert!(req.headers().is_empty());
+
+    let req = Request::parse_http1(
+        b"POST http://example.com/ HTTP/1.1\r\ntransfer-encoding: chunked\r\n\r\n0\r\n\r\n",
+    )?;
+    assert!(req.body().is_none());
+
+    // PUT requires a body, so an empty one is kept and needs a content type like any other
+    let req = Request::parse_http1(
+        b"PUT http://example.com/ HTTP/1.1\r\ncontent-type: text/plain\r\ncontent-length: 0\r\n\r\n",
+    )?;
+    assert_eq!(req.body().and_then(Payload::as_str), Some(""));
+    assert!(
+        Request::parse_http1(b"PUT http://example.com/ HTTP/1.1\r\ncontent-length: 0\r\n\r\n")
+            .is_err()
+    );
+    Ok(())
+}
+
+#[test]
+fn stream_bodies_cannot_be_serialized() -> anyhow::Result<()> {
+    let req = Request::from_parts(Parts {
+        url: Url::parse("http://example.com/")?,
+        method: Method::POST,
+        headers: HeaderMap::new(),
+        body: Some(Payload::stream(["a"])),
+        timeout: None,
+    });
+    assert!(req.to_http1_bytes().is_err());
+    Ok(())
+}